@@ -0,0 +1,53 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+
+#include <stdio.h>
+#include <string.h>
+
+#include "x800.h"
+
+/* Plays one game through the C interface, exiting non-zero on any surprise */
+int main(void) {
+    if (x800_abi_version() != X800_ABI_VERSION)
+        return 1;
+    if (x800_new("--topology=sphere", 1) != NULL)
+        return 2;
+
+    x800_game *game = x800_new("--merge=fibonacci", 42);
+    if (game == NULL || x800_width(game) != 4 || x800_height(game) != 4)
+        return 3;
+    if (x800_move(game, X800_IN) != X800_UNCHANGED)
+        return 4;
+    while (!x800_over(game))
+        for (uint32_t direction = X800_UP; direction <= X800_RIGHT; direction++)
+            if (x800_legal(game, direction) && x800_move(game, direction) == X800_ERROR)
+                return 5;
+
+    char state[1024];
+    size_t length = x800_serialise(game, state, sizeof state);
+    if (length == 0 || length >= sizeof state || strncmp(state, "{\"board\":", 9) != 0)
+        return 6;
+    printf("%u\n", x800_score(game));
+    x800_free(game);
+    return 0;
+}
@@ -0,0 +1,118 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+#ifndef X800_H
+#define X800_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Version of this interface, which changes whenever it does in a way
+   earlier programs would notice. x800_abi_version gives the version the
+   library was built with */
+#define X800_ABI_VERSION 1
+
+/* One game of x800, created by x800_new and freed by x800_free. Should the
+   engine fail within a call, the call returns NULL, X800_ERROR, false or 0,
+   or reports the game as over, rather than ending the program */
+typedef struct x800_game x800_game;
+
+/* Directions of a move. Square boards move in the first four, hex boards
+   move up, down and in the four diagonals, and cubes also move in and out */
+enum x800_direction {
+    X800_UP = 0,
+    X800_DOWN = 1,
+    X800_LEFT = 2,
+    X800_RIGHT = 3,
+    X800_UP_LEFT = 4,
+    X800_UP_RIGHT = 5,
+    X800_DOWN_LEFT = 6,
+    X800_DOWN_RIGHT = 7,
+    X800_IN = 8,
+    X800_OUT = 9,
+};
+
+/* Results of x800_move */
+enum x800_result {
+    X800_ERROR = -1,
+    X800_UNCHANGED = 0,
+    X800_CHANGED = 1,
+    X800_OVER = 2,
+};
+
+/* Cells of x800_cell holding a joker, bomb or doubler, and holding a rock or
+   outside the board's shape */
+#define X800_SPECIAL UINT32_C(0xfffffffe)
+#define X800_BLOCKED UINT32_C(0xffffffff)
+
+uint32_t x800_abi_version(void);
+
+/* Create a game from command-line options such as "--merge=fibonacci
+   --topology=torus", or NULL for the defaults, with tiles drawn from seed.
+   Returns NULL if the options are invalid */
+x800_game *x800_new(const char *options, uint64_t seed);
+
+/* Free a game. NULL is ignored */
+void x800_free(x800_game *game);
+
+/* Start a new game with the same options */
+void x800_reset(x800_game *game, uint64_t seed);
+
+/* Move in an x800_direction, returning an x800_result. Moves which change
+   nothing, or aren't on the board, leave the game as it was */
+int x800_move(x800_game *game, uint32_t direction);
+
+/* Whether a move would change the board */
+bool x800_legal(const x800_game *game, uint32_t direction);
+
+size_t x800_width(const x800_game *game);
+size_t x800_height(const x800_game *game);
+
+/* Value of the tile in a cell, 0 if it's empty, or X800_SPECIAL or
+   X800_BLOCKED. Cells off the board are blocked */
+uint32_t x800_cell(const x800_game *game, size_t row, size_t column);
+
+uint32_t x800_score(const x800_game *game);
+
+/* Number of moves which changed the board */
+uint32_t x800_moves(const x800_game *game);
+
+/* Whether the target has been reached */
+bool x800_won(const x800_game *game);
+
+bool x800_over(const x800_game *game);
+
+/* Write the game's state as JSON, as with --machine, like snprintf: at most
+   size bytes including a NUL are written, and the length of the whole state
+   is returned */
+size_t x800_serialise(const x800_game *game, char *buffer, size_t size);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
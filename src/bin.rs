@@ -20,22 +20,25 @@
   SOFTWARE.
 */
 
-mod board;
-mod colour;
-mod play;
+use std::{env, process};
 
-use libc;
-use std::process;
-
-use play::{play, Input};
+use x800::{run, Config, GAME_FAILURE_MESSAGE, USAGE};
 
 fn main() {
-    match play(&Input::Interactive) {
+    let config = match Config::from_args(env::args().skip(1)) {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("x800: {message}\n{USAGE}");
+            process::exit(libc::EXIT_FAILURE);
+        }
+    };
+
+    match run(&config) {
         Err(_) => {
-            eprintln!("{}", board::constants::GAME_FAILURE_MESSAGE);
+            eprintln!("{GAME_FAILURE_MESSAGE}");
             process::exit(libc::EXIT_FAILURE);
         }
-        Ok(_) => {
+        Ok(()) => {
             process::exit(libc::EXIT_SUCCESS);
         }
     }
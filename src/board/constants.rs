@@ -20,12 +20,18 @@
   SOFTWARE.
 */
 
-use super::tile::{CornerSide, EdgeSide, Tile, Tiles};
-use super::Board;
+pub(crate) use super::topology::MAX_HEX_RADIUS;
 use super::Power;
 
 // Internal constants
 pub(super) const EMPTY_TILE: &str = "      ";
+pub(super) const TILE_WIDTH: usize = EMPTY_TILE.len();
+// Four layers of a cube side by side fit in eighty columns with narrower
+// tiles, as does an opponent's board beside the player's
+pub(super) const NARROW_TILE_WIDTH: usize = 4;
+pub(super) const ROCK_TILE: &str = "░░░░░░";
+pub(super) const JOKER_TILE: &str = "joker ";
+pub(super) const BOMB_TILE: &str = " bomb ";
 pub(super) const LEFT_EDGE: &str = "│";
 pub(super) const RIGHT_EDGE: &str = "│\r\n";
 pub(super) const TOP_EDGE: &str = "──────";
@@ -35,13 +41,41 @@ pub(super) const BOTTOM_RIGHT_CORNER: &str = "┘\r\n";
 pub(super) const TOP_RIGHT_CORNER: &str = "┐\r\n";
 pub(super) const BOTTOM_LEFT_CORNER: &str = "└";
 pub(super) const TOP_LEFT_CORNER: &str = "┌";
+// Parts of a cell on a hex board
+pub(super) const HEX_FLOOR: &str = "______";
+pub(super) const HEX_GAP: &str = "        ";
+// Frame of narrow tiles, around each layer of a cube
+pub(super) const NARROW_TOP: &str = "┌────────────────┐";
+pub(super) const NARROW_BOTTOM: &str = "└────────────────┘";
+pub(super) const NARROW_SIDE: &str = "│";
+// Edges a torus wraps across
+pub(super) const WRAPPED_LEFT_EDGE: &str = "┆";
+pub(super) const WRAPPED_RIGHT_EDGE: &str = "┆\r\n";
+pub(super) const WRAPPED_TOP_EDGE: &str = "┄┄┄┄┄┄";
+pub(super) const WRAPPED_BOTTOM_EDGE: &str = "┄┄┄┄┄┄";
 pub(super) const LR_EDGE_WIDTH: usize = 1;
-pub(super) const SCORE_TEXT: &str = "score is ";
-pub(super) const WIN_MESSAGE: &str = "- - - - you win!! - - - -\r\n";
-pub(super) const WIN_POWER: Power = 11;
+pub(crate) const WIN_MESSAGE: &str = "- - - - you win!! - - - -\r\n";
+pub(crate) const GAME_OVER_PROMPT: &str = "game over  n: new  r: replay  q: quit\r\n";
+pub(crate) const WIN_PROMPT: &str = "you win!! k: keep going  q: stop\r\n";
+pub(crate) const PUZZLE_SOLVED_PROMPT: &str = "solved!  r: retry  q: quit\r\n";
+pub(crate) const PUZZLE_FAILED_PROMPT: &str = "not solved  r: retry  q: quit\r\n";
+pub(crate) const PLAYER_ONE_WINS: &str = "- - - player one wins - - -\r\n";
+pub(crate) const PLAYER_TWO_WINS: &str = "- - - player two wins - - -\r\n";
+pub(crate) const PLAYERS_DRAW: &str = "- - - - - - a draw - - - - -\r\n";
+pub(crate) const ONLINE_OVER_PROMPT: &str = "game over  q: quit\r\n";
+pub(crate) const RACE_WON: &str = "- - - you win the race - - -\r\n";
+pub(crate) const RACE_LOST: &str = "- - - - opponent wins - - - -\r\n";
+pub(crate) const OPPONENT_LEFT: &str = "- - - - opponent left - - - -\r\n";
+pub(super) const WINDOW_TOO_SMALL: &str = "enlarge the window to ";
+pub(crate) const WAITING_FOR_OPPONENT: &str = "waiting for the opponent\r\n";
+pub(crate) const MAX_WIN_POWER: Power = 30;
+// Highest number a special tile makes, whose classic value fits a u32
+pub(crate) const MAX_SPECIAL_POWER: Power = 31;
 pub(super) const TILES_WIDTH: usize = 4;
 pub(super) const BOARD_DIMENSION: usize = NUMBER_TILES_PER_LINE + 2 * LR_EDGE_WIDTH;
 pub(super) const DISPLAY_BUFFER_SIZE: usize = (BOARD_DIMENSION + 6) * (BOARD_DIMENSION + 4);
+// Longest line on any board, across the middle of the largest hexagon
+pub(super) const MAX_LINE_LENGTH: usize = 2 * MAX_HEX_RADIUS + 1;
 pub(super) const NUMBER_TILES_RANGE: std::ops::Range<usize> =
     LR_EDGE_WIDTH..(LR_EDGE_WIDTH + NUMBER_TILES_PER_LINE);
 
@@ -50,51 +84,6 @@ pub const NUMBER_TILES_PER_LINE: usize = 4;
 pub const LEFT_SPACE: &str = "\r      ";
 pub const GAME_OVER: &str = "- - - - game over - - - -\r\n";
 pub const GAME_FAILURE_MESSAGE: &str = " an ongoing game problem \r\n";
-pub const END_OF_GAME_CHARACTER: u8 = '\u{3}' as u8;
-pub const DISPLAY_LINE_LENGTH: usize =
-    (NUMBER_TILES_PER_LINE * TILES_WIDTH) + (2 * LR_EDGE_WIDTH) + LEFT_SPACE.len()
-        - SCORE_TEXT.len();
-
-pub const DEFAULT_BOARD: &Board = &Board {
-    tiles: Tiles([
-        Tile::Corner(CornerSide::TopLeft),
-        Tile::Edge(EdgeSide::Top),
-        Tile::Edge(EdgeSide::Top),
-        Tile::Edge(EdgeSide::Top),
-        Tile::Edge(EdgeSide::Top),
-        Tile::Corner(CornerSide::TopRight),
-        Tile::Edge(EdgeSide::Left),
-        Tile::Empty(),
-        Tile::Empty(),
-        Tile::Empty(),
-        Tile::Empty(),
-        Tile::Edge(EdgeSide::Right),
-        Tile::Edge(EdgeSide::Left),
-        Tile::Empty(),
-        Tile::Empty(),
-        Tile::Empty(),
-        Tile::Empty(),
-        Tile::Edge(EdgeSide::Right),
-        Tile::Edge(EdgeSide::Left),
-        Tile::Empty(),
-        Tile::Empty(),
-        Tile::Empty(),
-        Tile::Empty(),
-        Tile::Edge(EdgeSide::Right),
-        Tile::Edge(EdgeSide::Left),
-        Tile::Empty(),
-        Tile::Empty(),
-        Tile::Empty(),
-        Tile::Empty(),
-        Tile::Edge(EdgeSide::Right),
-        Tile::Corner(CornerSide::BottomLeft),
-        Tile::Edge(EdgeSide::Bottom),
-        Tile::Edge(EdgeSide::Bottom),
-        Tile::Edge(EdgeSide::Bottom),
-        Tile::Edge(EdgeSide::Bottom),
-        Tile::Corner(CornerSide::BottomRight),
-    ]),
-    open_tiles: 16,
-    score: 0,
-    max_tile: 0,
-};
+pub const END_OF_GAME_CHARACTER: u8 = b'\x03';
+pub const STATUS_WIDTH: usize =
+    (NUMBER_TILES_PER_LINE * TILES_WIDTH) + (2 * LR_EDGE_WIDTH) + LEFT_SPACE.len();
@@ -0,0 +1,62 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::fmt::{self, Write};
+
+use super::constants::{
+    LEFT_SPACE, NARROW_BOTTOM, NARROW_SIDE, NARROW_TILE_WIDTH, NARROW_TOP, NUMBER_TILES_PER_LINE,
+};
+use super::topology::LAYER_STRIDE;
+use super::Board;
+
+/// Drawing for cubes
+///
+/// Layers are drawn side by side in their own frames, from the outermost
+/// on the left to the innermost on the right.
+impl Board {
+    pub(super) fn draw_cube(&self, buffer: &mut String) -> fmt::Result {
+        let bottom = self.tiles.cells().len() / self.tiles.width - 1;
+
+        for r in 0..=bottom {
+            buffer.push_str(LEFT_SPACE);
+            for layer in 0..NUMBER_TILES_PER_LINE {
+                match r {
+                    0 => buffer.push_str(NARROW_TOP),
+                    _ if r == bottom => buffer.push_str(NARROW_BOTTOM),
+                    _ => {
+                        buffer.push_str(NARROW_SIDE);
+                        for c in layer * LAYER_STRIDE + 1..(layer + 1) * LAYER_STRIDE {
+                            write!(
+                                buffer,
+                                "{}",
+                                self.shown(self.tiles[(r, c)], NARROW_TILE_WIDTH)
+                            )?;
+                        }
+                        buffer.push_str(NARROW_SIDE);
+                    }
+                }
+            }
+            buffer.push_str("\r\n");
+        }
+        Ok(())
+    }
+}
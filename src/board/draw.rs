@@ -21,36 +21,125 @@
 */
 
 use super::super::colour::Colour;
-use super::constants::{self, BOARD_DIMENSION, NUMBER_TILES_PER_LINE};
-use super::Board;
+use super::constants::{
+    self, NARROW_BOTTOM, NARROW_SIDE, NARROW_TILE_WIDTH, NARROW_TOP, NUMBER_TILES_RANGE, TILE_WIDTH,
+};
+use super::{
+    tile::{Shown, Tile},
+    Board, Topology,
+};
+use crate::{
+    game::Game,
+    spectate::Publisher,
+    status::{self, Layout, TICK_DURATION},
+    terminal::FrameSink,
+};
 use std::{
     fmt,
     fmt::Write as FmtWrite,
-    io::Write,
-    sync::{atomic, Arc, Mutex},
-    thread, time,
+    iter,
+    sync::{
+        atomic::{self, AtomicU16},
+        Arc, Mutex, OnceLock,
+    },
+    thread::{self, Thread},
+    time,
 };
 
-pub fn draw(board: Arc<Mutex<Board>>, done: &Arc<atomic::AtomicBool>) -> fmt::Result {
-    // When fuzzing, this fn should not be called
-    assert!(cfg!(not(fuzzing)));
+/// What the drawing thread shows: one game, or several sharing the screen
+pub trait Screen {
+    /// Draw one whole frame into `buffer`
+    fn draw_frame(&self, layout: &Layout, buffer: &mut String) -> fmt::Result;
+}
 
-    // Use one buffer for program duration
-    let buffer = &mut String::with_capacity(constants::DISPLAY_BUFFER_SIZE);
+impl Screen for Mutex<Game> {
+    fn draw_frame(&self, layout: &Layout, buffer: &mut String) -> fmt::Result {
+        draw_game(&self.lock().unwrap(), layout, buffer)
+    }
+}
+
+/// Size of a player's window, for terminals which report it. Zero until then
+#[derive(Default)]
+pub struct Window {
+    columns: AtomicU16,
+    rows: AtomicU16,
+    /// The drawing thread, woken to redraw at the new size
+    drawing: OnceLock<Thread>,
+}
+
+impl Window {
+    pub fn size(&self) -> (u16, u16) {
+        (
+            self.columns.load(atomic::Ordering::Relaxed),
+            self.rows.load(atomic::Ordering::Relaxed),
+        )
+    }
+
+    pub fn resize(&self, columns: u16, rows: u16) {
+        self.columns.store(columns, atomic::Ordering::Relaxed);
+        self.rows.store(rows, atomic::Ordering::Relaxed);
+        if let Some(drawing) = self.drawing.get() {
+            drawing.unpark();
+        }
+    }
+
+    /// Columns and rows needed for `frame`, if it doesn't fit
+    fn shortfall(&self, frame: &str) -> Option<(usize, usize)> {
+        let (window_columns, window_rows) = self.size();
+        // Not reported yet
+        if window_columns == 0 {
+            return None;
+        }
+        let columns = frame
+            .split('\n')
+            .map(|line| visible_width(line.trim_matches('\r')))
+            .max()
+            .unwrap_or(0);
+        let rows = frame.trim_end().split('\n').count();
+        let fits = columns <= usize::from(window_columns) && rows <= usize::from(window_rows);
+        (!fits).then_some((columns, rows))
+    }
+}
+
+/// Where the drawing thread sends its frames
+pub struct Output {
+    /// Frames too large for its window are replaced by a request for room
+    pub sink: Box<dyn FrameSink>,
+    pub publisher: Option<Publisher>,
+}
 
+// When fuzzing, this fn should not be called
+pub fn draw<S: Screen + ?Sized>(
+    screen: &S,
+    layout: &Layout,
+    mut output: Output,
+    done: &Arc<atomic::AtomicBool>,
+) -> fmt::Result {
     // Duration between draws. 2ms is 500Hz
     const DRAW_DURATION: time::Duration = time::Duration::from_millis(2);
 
+    // Use one buffer for program duration
+    let buffer = &mut String::with_capacity(constants::DISPLAY_BUFFER_SIZE);
+
     // If set, draw the board this time
     let mut force_draw = true;
 
     // Always draw the first time
     let mut timestamp = time::Instant::now();
     let mut exit_after = false;
+    let ticking = layout.is_ticking();
+    let window = output.sink.window();
+    if let Some(window) = &window {
+        window.drawing.get_or_init(thread::current);
+    }
 
     loop {
-        // Wait for wakeup
-        thread::park();
+        // Wait for wakeup, or for the next tick of the clock
+        if ticking {
+            thread::park_timeout(TICK_DURATION);
+        } else {
+            thread::park();
+        }
 
         // Check if we should exit this time
         if done.load(atomic::Ordering::Relaxed) {
@@ -60,27 +149,32 @@ pub fn draw(board: Arc<Mutex<Board>>, done: &Arc<atomic::AtomicBool>) -> fmt::Re
 
         // Continue waiting if time has not elapsed and a draw is not required
         // Note that we use the monotonic timestamp, time::Instant()
-        if !force_draw && (time::Instant::now() - timestamp) < DRAW_DURATION {
+        if !force_draw && timestamp.elapsed() < DRAW_DURATION {
             continue;
-        } else {
-            force_draw = false;
-            timestamp = time::Instant::now();
         }
+        force_draw = false;
+        timestamp = time::Instant::now();
 
-        // Limit scope to avoid holding the lock
-        {
-            // Alias for reading
-            let board = board.lock().unwrap();
+        // Locks are released before writing out
+        Board::draw_clear(buffer)?;
+        screen.draw_frame(layout, buffer)?;
 
-            // Write out to string
-            board.draw_clear(buffer)?;
-            board.draw_header(buffer)?;
-            board.draw_tiles(buffer)?;
-            board.draw_score(buffer)?;
+        // Write out framebuffer, or ask for more room
+        let shortfall = window.as_ref().and_then(|window| window.shortfall(buffer));
+        if let Some(publisher) = &output.publisher {
+            publisher.publish(buffer);
         }
-
-        // Write out framebuffer
-        write!(std::io::stdout(), "{}", buffer).expect("failed to render");
+        if let Some((columns, rows)) = shortfall {
+            buffer.clear();
+            Board::draw_clear(buffer)?;
+            write!(
+                buffer,
+                "{}{}{columns}x{rows}\r\n",
+                constants::LEFT_SPACE,
+                constants::WINDOW_TOO_SMALL
+            )?;
+        }
+        output.sink.write_frame(buffer).expect("failed to render");
         buffer.clear();
 
         // Leave the loop
@@ -89,29 +183,107 @@ pub fn draw(board: Arc<Mutex<Board>>, done: &Arc<atomic::AtomicBool>) -> fmt::Re
         }
     }
 
+    // A farewell beneath the final frame
+    buffer.push_str(constants::LEFT_SPACE);
+    buffer.push_str(constants::GAME_OVER);
+    if let Some(publisher) = &output.publisher {
+        publisher.publish(buffer);
+    }
+    output.sink.write_frame(buffer).expect("failed to render");
     Ok(())
 }
 
+/// Board and status area of one game
+pub fn draw_game(game: &Game, layout: &Layout, buffer: &mut String) -> fmt::Result {
+    game.board.draw_header(buffer)?;
+    game.board.draw_tiles(buffer)?;
+    status::draw_banner(game, buffer)?;
+    layout.draw(game, buffer)
+}
+
+/// Lay out frames side by side, line by line
+pub fn compose(frames: &[String], buffer: &mut String) {
+    let frames: Vec<Vec<&str>> = frames
+        .iter()
+        .map(|frame| {
+            frame
+                .trim_end()
+                .split('\n')
+                .map(|line| line.trim_matches('\r'))
+                .collect()
+        })
+        .collect();
+    let widths: Vec<usize> = frames
+        .iter()
+        .map(|lines| {
+            lines
+                .iter()
+                .map(|line| visible_width(line))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let height = frames.iter().map(Vec::len).max().unwrap_or(0);
+
+    for row in 0..height {
+        buffer.push('\r');
+        for (lines, &width) in frames.iter().zip(&widths) {
+            let line = lines.get(row).copied().unwrap_or_default();
+            buffer.push_str(line);
+            buffer.extend(iter::repeat_n(' ', width - visible_width(line)));
+        }
+        buffer.push_str("\r\n");
+    }
+}
+
+// Characters on screen, not counting colour escape codes
+fn visible_width(line: &str) -> usize {
+    let mut escape = false;
+    line.chars()
+        .filter(|&c| {
+            if escape {
+                escape = !c.is_ascii_alphabetic();
+                false
+            } else {
+                escape = c == '\u{1B}';
+                !escape
+            }
+        })
+        .count()
+}
+
 impl Board {
-    pub fn draw_score(&self, buffer: &mut String) -> fmt::Result {
-        let space = constants::LEFT_SPACE;
-        let score_colour = Colour::from_power(self.max_tile);
-        let score_text = constants::SCORE_TEXT;
-        let length = constants::DISPLAY_LINE_LENGTH;
-        let no_colour = Colour::default();
-        let header = if self.max_tile >= constants::WIN_POWER {
-            constants::WIN_MESSAGE
-        } else {
-            "\r\n"
-        };
+    /// A tile ready for drawing, `width` characters across
+    #[inline]
+    pub(super) fn shown(&self, tile: Tile, width: usize) -> Shown {
+        Shown {
+            tile,
+            rule: self.rule,
+            topology: self.topology,
+            width,
+        }
+    }
 
-        write!(
-            buffer,
-            "{before}{score:<length$}{after}",
-            score = self.score,
-            before = format_args!("{space}{header}{space}{score_colour}{score_text}"),
-            after = format_args!("{no_colour}\r\n"),
-        )
+    /// A square board in narrow tiles, each line starting with `margin`
+    pub fn draw_mini(&self, margin: &str, buffer: &mut String) -> fmt::Result {
+        let bottom = self.tiles.cells().len() / self.tiles.width - 1;
+
+        for (r, row) in self.tiles.cells().chunks(self.tiles.width).enumerate() {
+            buffer.push_str(margin);
+            match r {
+                0 => buffer.push_str(NARROW_TOP),
+                _ if r == bottom => buffer.push_str(NARROW_BOTTOM),
+                _ => {
+                    buffer.push_str(NARROW_SIDE);
+                    for &tile in &row[NUMBER_TILES_RANGE] {
+                        write!(buffer, "{}", self.shown(tile, NARROW_TILE_WIDTH))?;
+                    }
+                    buffer.push_str(NARROW_SIDE);
+                }
+            }
+            buffer.push_str("\r\n");
+        }
+        Ok(())
     }
 
     fn draw_header(&self, buffer: &mut String) -> fmt::Result {
@@ -124,22 +296,21 @@ impl Board {
                 Colour::from_power(self.max_tile),
                 "",
                 Colour::default(),
-                colour_len = (NUMBER_TILES_PER_LINE * constants::TILES_WIDTH)
-                    + (2 * constants::LR_EDGE_WIDTH)
-                    + constants::LEFT_SPACE.len()
+                colour_len = constants::STATUS_WIDTH
             )
         )
     }
 
     fn draw_tiles(&self, buffer: &mut String) -> fmt::Result {
-        // Allow bounds-checking elision
-        assert_eq!(self.tiles.0.len(), BOARD_DIMENSION * BOARD_DIMENSION);
+        match self.topology {
+            Topology::Hex(radius) => return self.draw_hex(buffer, radius),
+            Topology::Cube => return self.draw_cube(buffer),
+            Topology::Flat | Topology::Torus => {}
+        }
 
-        // Iterate over each row and column, then print
-        for i in 0..BOARD_DIMENSION {
-            for j in 0..BOARD_DIMENSION {
-                write!(buffer, "{}", self.tiles[(i, j)])?;
-            }
+        // Print each row and column in order
+        for &tile in self.tiles.cells() {
+            write!(buffer, "{}", self.shown(tile, TILE_WIDTH))?;
         }
         Ok(())
     }
@@ -0,0 +1,124 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use super::{spawn::Spawn, Board, Generation, SpawnRules};
+
+/// Adversarial tile placement
+///
+/// Rather than choosing at random, the "evil" spawner places the tile
+/// whose cell and value leave the player with the worst best reply. This
+/// is a shallow minimax: the player maximises and the spawner minimises a
+/// simple evaluation, searching `depth` player moves ahead.
+// Each empty cell is worth about as much as merging two '32' tiles
+const EMPTY_WEIGHT: i64 = 64;
+// Boards with more empty cells than a classic board can hold are searched
+// one move ahead, so that large boards answer moves without lag
+const MAX_DEEP_SEARCH_CELLS: u32 = 16;
+
+impl Board {
+    pub fn spawn_evil(&mut self, generation: Generation, rules: &SpawnRules, depth: u32) -> bool {
+        if !self.has_space() {
+            return false;
+        }
+
+        let depth = if self.open_tiles > MAX_DEEP_SEARCH_CELLS {
+            depth.min(1)
+        } else {
+            depth
+        };
+        let (cell, spawn, _) = self.worst_spawn(generation, rules, depth);
+        self.place_spawn(cell, spawn.tile(generation));
+        true
+    }
+
+    fn evaluate(&self) -> i64 {
+        i64::from(self.open_tiles) * EMPTY_WEIGHT + i64::from(self.score)
+    }
+
+    /// Spawn which minimises the player's best reply, along with its value
+    fn worst_spawn(
+        &self,
+        generation: Generation,
+        rules: &SpawnRules,
+        depth: u32,
+    ) -> ((usize, usize), Spawn, i64) {
+        let mut worst = ((0, 0), Spawn::Number(1), i64::MAX);
+
+        for cell in self.empty_cells() {
+            for spawn in rules.possible() {
+                let mut next = self.clone();
+                next.place(cell, spawn.tile(generation));
+
+                let value = next.best_reply(generation, rules, depth);
+                if value < worst.2 {
+                    worst = (cell, spawn, value);
+                }
+            }
+        }
+        worst
+    }
+
+    /// Value of the best move for the player, or the minimum when stuck
+    fn best_reply(&self, generation: Generation, rules: &SpawnRules, depth: u32) -> i64 {
+        let generation = generation + 1;
+        let mut best = i64::MIN;
+
+        for &direction in self.topology.directions() {
+            let mut next = self.clone();
+            if !next.update(direction, generation) {
+                continue;
+            }
+
+            let value = if depth <= 1 || !next.has_space() {
+                next.evaluate()
+            } else {
+                next.worst_spawn(generation, rules, depth - 1).2
+            };
+            best = best.max(value);
+        }
+        best
+    }
+}
+
+#[test]
+fn spawn_evil_blocks_test() {
+    use super::constants::NUMBER_TILES_RANGE;
+    use super::{merge::Classic, tile::Tile, Power, Topology};
+
+    // A checkerboard of '2' and '4' tiles, missing the last cell
+    let mut board = Board::new(&Classic, Topology::Flat);
+    for r in NUMBER_TILES_RANGE {
+        for c in NUMBER_TILES_RANGE {
+            board.place((r, c), Tile::Number(1 + Power::from((r + c) % 2 == 1), 0));
+        }
+    }
+    board.tiles[(4, 4)] = Tile::Empty();
+    board.open_tiles = 1;
+
+    // Only a '2' there leaves no moves, unless '2' can't spawn
+    let mut blocked = board.clone();
+    assert!(blocked.spawn_evil(1, &SpawnRules::default(), 2));
+    assert!(blocked.tiles[(4, 4)] == Tile::Number(1, 1));
+    let rules = SpawnRules::parse("4:1,2:0", &Classic).unwrap();
+    assert!(board.spawn_evil(1, &rules, 2));
+    assert!(board.tiles[(4, 4)] == Tile::Number(2, 1));
+}
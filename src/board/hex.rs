@@ -0,0 +1,79 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::fmt::{self, Write};
+
+use super::constants::{HEX_FLOOR, HEX_GAP, LEFT_SPACE, ROCK_TILE, TILE_WIDTH};
+use super::tile::Tile;
+use super::Board;
+use crate::colour::Colour;
+
+/// Drawing for hex boards
+///
+/// Each column of cells is drawn half a cell lower than the one to its
+/// left, so that cells meet their six neighbours. A cell is two lines
+/// tall, with its tile on the upper line:
+///
+/// ```text
+/// /  16  \
+/// \______/
+/// ```
+impl Board {
+    pub(super) fn draw_hex(&self, buffer: &mut String, radius: usize) -> fmt::Result {
+        let radius = radius.cast_signed();
+
+        for line in 0..=4 * radius + 1 {
+            buffer.push_str(LEFT_SPACE);
+            for q in -radius..=radius {
+                // Rows run down-right, so each column starts half a cell lower
+                let offset = line - q - 2 * radius;
+                let r = offset.div_euclid(2);
+                let cell = (
+                    (r + radius + 1).cast_unsigned(),
+                    (q + radius + 1).cast_unsigned(),
+                );
+
+                if r.abs() > radius || self.tiles[cell].is_frame() {
+                    buffer.push_str(HEX_GAP);
+                } else if offset % 2 == 0 {
+                    write!(buffer, "/{}\\", self.shown(self.tiles[cell], TILE_WIDTH))?;
+                } else {
+                    let (colour, fill) = lower(self.tiles[cell]);
+                    write!(buffer, "\\{colour}{fill}{}/", Colour::default())?;
+                }
+            }
+            buffer.push_str("\r\n");
+        }
+        Ok(())
+    }
+}
+
+// Lower half of a cell, filled in the colour of its tile
+fn lower(tile: Tile) -> (Colour, &'static str) {
+    match tile {
+        Tile::Number(power, _) => (Colour::from_power(power), HEX_FLOOR),
+        Tile::Rock() => (Colour::ROCK, ROCK_TILE),
+        Tile::Joker() | Tile::Double(_) => (Colour::SPECIAL, HEX_FLOOR),
+        Tile::Bomb() => (Colour::BOMB, HEX_FLOOR),
+        _ => (Colour::default(), HEX_FLOOR),
+    }
+}
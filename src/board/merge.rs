@@ -0,0 +1,231 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use super::Power;
+
+/// How number tiles merge, and what they're worth
+///
+/// Tiles store a level rather than a value, so each rule decides which
+/// levels merge, and what a tile of each level is worth and shows.
+pub trait MergeRule: Sync {
+    /// Name used on the command line and in records
+    fn name(&self) -> &'static str;
+
+    /// Number of neighbouring tiles which merge at once
+    fn run(&self) -> usize {
+        2
+    }
+
+    /// Level of the tile made from a run of neighbouring levels, if they merge
+    fn merge(&self, levels: &[Power]) -> Option<Power>;
+
+    /// Value of a tile, which is also scored when it's made
+    fn value(&self, level: Power) -> u32;
+
+    fn label(&self, level: Power) -> String {
+        self.value(level).to_string()
+    }
+
+    /// Spawn table used by the "classic" preset
+    fn spawn(&self) -> &'static str;
+
+    /// Level of the tile with this value, if there is one
+    fn level(&self, value: u32) -> Option<Power> {
+        (1..=MAX_LEVEL).find(|&level| self.value(level) == value)
+    }
+
+    /// Level of the first tile worth at least `value`
+    fn level_at_least(&self, value: u32) -> Power {
+        (1..=MAX_LEVEL)
+            .find(|&level| self.value(level) >= value)
+            .unwrap_or(MAX_LEVEL)
+    }
+
+    /// Level of the first tile worth at least 2048, which wins by default
+    fn target(&self) -> Power {
+        self.level_at_least(TARGET_VALUE)
+    }
+}
+
+const MAX_LEVEL: Power = 32;
+const TARGET_VALUE: u32 = 2048;
+
+/// Equal powers of two merge, as in 2048
+pub struct Classic;
+
+impl MergeRule for Classic {
+    fn name(&self) -> &'static str {
+        "classic"
+    }
+
+    #[inline]
+    fn merge(&self, levels: &[Power]) -> Option<Power> {
+        match *levels {
+            [n, m] if n == m => Some(n + 1),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn value(&self, level: Power) -> u32 {
+        1_u32.checked_shl(level).unwrap_or(u32::MAX)
+    }
+
+    fn spawn(&self) -> &'static str {
+        "2:3,4:1/1/2"
+    }
+}
+
+/// Neighbouring Fibonacci numbers merge: 1, 2, 3, 5, 8 and so on
+pub struct Fibonacci;
+
+impl MergeRule for Fibonacci {
+    fn name(&self) -> &'static str {
+        "fibonacci"
+    }
+
+    fn merge(&self, levels: &[Power]) -> Option<Power> {
+        match *levels {
+            // Two ones make two
+            [1, 1] => Some(2),
+            [n, m] if n.abs_diff(m) == 1 => Some(n.max(m) + 1),
+            _ => None,
+        }
+    }
+
+    fn value(&self, level: Power) -> u32 {
+        let (mut previous, mut value) = (1_u32, 1_u32);
+        for _ in 0..level {
+            (previous, value) = (value, value.saturating_add(previous));
+        }
+        previous
+    }
+
+    fn spawn(&self) -> &'static str {
+        "1:3,2:1/1/2"
+    }
+}
+
+/// Three equal powers of three merge into the next
+pub struct Triples;
+
+impl MergeRule for Triples {
+    fn name(&self) -> &'static str {
+        "triples"
+    }
+
+    fn run(&self) -> usize {
+        3
+    }
+
+    fn merge(&self, levels: &[Power]) -> Option<Power> {
+        match *levels {
+            [n, m, o] if n == m && m == o => Some(n + 1),
+            _ => None,
+        }
+    }
+
+    fn value(&self, level: Power) -> u32 {
+        3_u32.saturating_pow(level)
+    }
+
+    fn spawn(&self) -> &'static str {
+        "3:1/1/3"
+    }
+}
+
+/// As in Threes: a one and a two make three, then equal tiles of three
+/// or more merge by doubling
+pub struct Threes;
+
+impl MergeRule for Threes {
+    fn name(&self) -> &'static str {
+        "threes"
+    }
+
+    fn merge(&self, levels: &[Power]) -> Option<Power> {
+        match *levels {
+            [1, 2] | [2, 1] => Some(3),
+            [n, m] if n == m && n >= 3 => Some(n + 1),
+            _ => None,
+        }
+    }
+
+    fn value(&self, level: Power) -> u32 {
+        match level {
+            0..=2 => level,
+            _ => 3_u32.saturating_mul(Classic.value(level - 3)),
+        }
+    }
+
+    fn spawn(&self) -> &'static str {
+        "1:2,2:2,3:1/1/3"
+    }
+}
+
+pub const RULES: [&dyn MergeRule; 4] = [&Classic, &Fibonacci, &Triples, &Threes];
+
+/// Find a rule by name
+pub fn find(name: &str) -> Option<&'static dyn MergeRule> {
+    RULES.into_iter().find(|rule| rule.name() == name)
+}
+
+#[test]
+fn merge_rules_test() {
+    assert_eq!(
+        (1..=8)
+            .map(|level| Fibonacci.value(level))
+            .collect::<Vec<_>>(),
+        [1, 2, 3, 5, 8, 13, 21, 34]
+    );
+    assert_eq!(
+        (1..=6).map(|level| Threes.value(level)).collect::<Vec<_>>(),
+        [1, 2, 3, 6, 12, 24]
+    );
+    assert_eq!(Fibonacci.merge(&[4, 5]), Some(6));
+    assert_eq!(Fibonacci.merge(&[4, 4]), None);
+    assert_eq!(Threes.merge(&[2, 2]), None);
+    assert_eq!(Triples.merge(&[2, 2]), None);
+
+    // Each rule wins at its first tile worth 2048 or more
+    let targets = RULES.map(|rule| rule.value(rule.target()));
+    assert_eq!(targets, [2048, 2584, 2187, 3072]);
+    assert_eq!(find("threes").and_then(|rule| rule.level(24)), Some(6));
+
+    // Runs of three merge from the front of a line
+    let mut board = super::Board::from_rows([
+        [Some(1); 4],
+        [Some(2), Some(1), Some(1), Some(1)],
+        [Some(0); 4],
+        [Some(0); 4],
+    ]);
+    board.rule = &Triples;
+    assert!(board.update(super::Direction::Left, 1));
+    assert_eq!(
+        board.rows()[..2],
+        [
+            [Some(2), Some(1), Some(0), Some(0)],
+            [Some(2), Some(2), Some(0), Some(0)]
+        ]
+    );
+    assert_eq!(board.score, 9 + 9);
+}
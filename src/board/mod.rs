@@ -21,24 +21,37 @@
 */
 
 use core::fmt;
-use std::{cmp, collections::BTreeSet, fmt::Write};
+use std::{cmp, fmt::Write, iter};
 
 pub mod constants;
-pub use draw::draw;
+pub use draw::{compose, draw, draw_game, Output, Screen, Window};
+pub use merge::MergeRule;
+pub use spawn::{Rocks, SpawnRules};
+pub(crate) use tile::Tile;
+pub use topology::Topology;
 
+mod cube;
 mod draw;
+mod evil;
+mod hex;
+pub mod merge;
+mod spawn;
 mod tile;
+mod topology;
 
 use self::constants::END_OF_GAME_CHARACTER;
 
 use super::colour::Colour;
-use tile::Tile;
+use tile::Merged;
 
 // Promote Power type to public within this module
 pub type Power = tile::Power;
 pub type Generation = tile::Power;
 
-use constants::{BOARD_DIMENSION, NUMBER_TILES_RANGE};
+use constants::{MAX_LINE_LENGTH, NUMBER_TILES_PER_LINE, NUMBER_TILES_RANGE};
+
+/// Rows of powers, where None is a rock and Some(0) is empty or special
+pub type Rows = [[Option<Power>; NUMBER_TILES_PER_LINE]; NUMBER_TILES_PER_LINE];
 
 #[derive(Clone)]
 pub struct Board {
@@ -46,40 +59,135 @@ pub struct Board {
     score: u32,
     open_tiles: u32,
     max_tile: Power,
+    rule: &'static dyn MergeRule,
+    topology: Topology,
+    /// Cells filled by spawns since the last move
+    spawned: Vec<(usize, usize)>,
 }
 
+/// Directions of a move. Square boards move in the first four, hex
+/// boards move up, down, and in the four diagonals, and cubes also move
+/// in and out between layers
 #[derive(Clone, Copy, PartialEq, Eq)]
-pub(super) enum Direction {
+pub enum Direction {
     Up,
     Down,
     Left,
     Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+    In,
+    Out,
+}
+
+impl Direction {
+    pub(crate) const ALL: [Direction; 10] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+        Direction::UpLeft,
+        Direction::UpRight,
+        Direction::DownLeft,
+        Direction::DownRight,
+        Direction::In,
+        Direction::Out,
+    ];
+
+    /// Name of the direction in text protocols
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+            Direction::UpLeft => "up-left",
+            Direction::UpRight => "up-right",
+            Direction::DownLeft => "down-left",
+            Direction::DownRight => "down-right",
+            Direction::In => "in",
+            Direction::Out => "out",
+        }
+    }
+
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|direction| direction.name() == name)
+    }
 }
 
-#[derive(PartialEq, Eq)]
-pub(super) enum Action {
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action {
     Direction(Direction),
+    Keep,
+    Stop,
+    NewGame,
+    Replay,
     Continue,
     Shutdown,
 }
 
 impl Action {
-    pub(super) const fn parse(input: u8) -> Self {
+    pub const fn parse(input: u8) -> Self {
         match input {
             b'w' => Action::Direction(Direction::Up),
             b'a' => Action::Direction(Direction::Left),
             b'd' => Action::Direction(Direction::Right),
             b's' => Action::Direction(Direction::Down),
+            b'k' => Action::Keep,
+            b'q' => Action::Stop,
+            b'n' => Action::NewGame,
+            b'r' => Action::Replay,
             END_OF_GAME_CHARACTER => Action::Shutdown,
             _ => Action::Continue,
         }
     }
+
+    /// Keys on a hex board, which has two moves on either side
+    pub const fn parse_hex(input: u8) -> Self {
+        match input {
+            b'q' => Action::Direction(Direction::UpLeft),
+            b'e' => Action::Direction(Direction::UpRight),
+            b'a' => Action::Direction(Direction::DownLeft),
+            b'd' => Action::Direction(Direction::DownRight),
+            _ => Self::parse(input),
+        }
+    }
+
+    /// Keys on a cube, where 'q' and 'e' move out and in between layers
+    pub const fn parse_cube(input: u8) -> Self {
+        match input {
+            b'q' => Action::Direction(Direction::Out),
+            b'e' => Action::Direction(Direction::In),
+            _ => Self::parse(input),
+        }
+    }
 }
 
 impl Board {
+    pub fn new(rule: &'static dyn MergeRule, topology: Topology) -> Self {
+        let tiles = topology.tiles();
+        let open_tiles = tiles.cells().iter().filter(|&&tile| tile == Tile::Empty());
+        Board {
+            open_tiles: u32::try_from(open_tiles.count()).expect("Board too large"),
+            tiles,
+            score: 0,
+            max_tile: 0,
+            rule,
+            topology,
+            spawned: Vec::new(),
+        }
+    }
+
     // Clear screen
+    #[allow(clippy::inline_always)]
     #[inline(always)]
-    pub fn draw_clear(&self, output: &mut String) -> fmt::Result {
+    pub fn draw_clear(output: &mut String) -> fmt::Result {
         write!(
             output,
             "{}",
@@ -87,173 +195,557 @@ impl Board {
         )
     }
 
+    #[allow(clippy::inline_always)]
     #[inline(always)]
     pub fn has_space(&self) -> bool {
         self.open_tiles != 0
     }
 
-    fn pair(major: usize, minor: usize, direction: Direction) -> ((usize, usize), (usize, usize)) {
-        match direction {
-            Direction::Left => ((major, minor), (major, minor + 1)),
-            Direction::Right => ((major, minor), (major, minor - 1)),
-            Direction::Up => ((minor, major), (minor + 1, major)),
-            Direction::Down => ((minor, major), (minor - 1, major)),
+    #[inline]
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    #[inline]
+    pub fn max_tile(&self) -> Power {
+        self.max_tile
+    }
+
+    #[inline]
+    pub fn rule(&self) -> &'static dyn MergeRule {
+        self.rule
+    }
+
+    #[inline]
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Rows of cells within the frame. Cells outside the board's shape are
+    /// `Tile::Void`
+    pub fn grid(&self) -> impl Iterator<Item = &[Tile]> {
+        let width = self.tiles.width;
+        let height = self.tiles.cells().len() / width;
+        self.tiles
+            .cells()
+            .chunks(width)
+            .take(height - 1)
+            .skip(1)
+            .map(move |row| &row[1..width - 1])
+    }
+
+    /// Cells of the grid filled since the last move, as row and column
+    pub fn spawned(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.spawned.iter().map(|&(r, c)| (r - 1, c - 1))
+    }
+
+    /// Whether a move would change the board, without making it
+    pub fn can_move(&self, direction: Direction, generation: Generation) -> bool {
+        self.clone().update(direction, generation)
+    }
+
+    /// Rows of a square board
+    pub fn rows(&self) -> Rows {
+        let mut rows = [[Some(0); NUMBER_TILES_PER_LINE]; NUMBER_TILES_PER_LINE];
+        for (r, row) in NUMBER_TILES_RANGE.zip(&mut rows) {
+            for (c, power) in NUMBER_TILES_RANGE.zip(row) {
+                *power = match self.tiles[(r, c)] {
+                    Tile::Number(power, _) => Some(power),
+                    Tile::Rock() => None,
+                    _ => Some(0),
+                };
+            }
+        }
+        rows
+    }
+
+    /// A classic board holding these rows
+    pub fn from_rows(rows: Rows) -> Self {
+        let mut board = Board::new(&merge::Classic, Topology::Flat);
+        for (r, row) in NUMBER_TILES_RANGE.zip(rows) {
+            for (c, power) in NUMBER_TILES_RANGE.zip(row) {
+                match power {
+                    Some(0) => {}
+                    Some(power) => board.place((r, c), Tile::Number(power, 0)),
+                    None => board.place((r, c), Tile::Rock()),
+                }
+            }
         }
+        board
     }
 
+    // Cells of the line starting at `front`, the cell nearest the edge which
+    // tiles move towards, walking back against the move
+    #[inline]
+    fn line(
+        &self,
+        front: (usize, usize),
+        (dr, dc): (isize, isize),
+        line: &mut [(usize, usize); MAX_LINE_LENGTH],
+    ) -> usize {
+        let mut cell = front;
+        let mut length = 0;
+        while !self.tiles.is_outside(cell) {
+            line[length] = cell;
+            length += 1;
+            cell = (
+                cell.0.wrapping_add_signed(-dr),
+                cell.1.wrapping_add_signed(-dc),
+            );
+        }
+        length
+    }
+
+    // Slide movable tiles to the front of a segment, closing any gaps
+    #[allow(clippy::inline_always)]
     #[inline(always)]
-    fn collect(
-        &mut self,
-        major: usize,
-        minor: usize,
-        direction: Direction,
-        set: &mut BTreeSet<(usize, usize)>,
-    ) -> bool {
-        let current = Self::pair(major, minor, direction).0;
+    fn collect(&mut self, segment: &[(usize, usize)]) -> bool {
         let mut moved = false;
+        let mut front = 0;
 
-        match self.tiles[current] {
-            Tile::Empty() => {
-                set.insert(current);
-            }
-            Tile::Number(_, _) => {
-                if !set.is_empty() {
-                    let index = match direction {
-                        Direction::Up | Direction::Left => set.pop_first(),
-                        Direction::Down | Direction::Right => set.pop_last(),
-                    }
-                    .expect("Malformed set");
-
-                    self.tiles[index] = self.tiles[current];
-                    self.tiles[current] = Tile::Empty();
-                    set.insert(current);
+        for (i, &cell) in segment.iter().enumerate() {
+            if self.tiles[cell].is_movable() {
+                if i != front {
+                    self.tiles[segment[front]] = self.tiles[cell];
+                    self.tiles[cell] = Tile::Empty();
                     moved = true;
                 }
+                front += 1;
             }
-            Tile::Corner(_) | Tile::Edge(_) => {}
         }
         moved
     }
 
+    // Level made by a run of number tiles, if the rule merges them
+    #[inline]
+    fn merge_numbers(&self, cells: &[(usize, usize)]) -> Option<Power> {
+        let mut levels = [0; MAX_LINE_LENGTH];
+        for (level, &cell) in levels.iter_mut().zip(cells) {
+            let Tile::Number(power, _) = self.tiles[cell] else {
+                return None;
+            };
+            *level = power;
+        }
+        self.rule.merge(&levels[..cells.len()])
+    }
+
+    // Merge each run of neighbours once, from the front of a collected segment
+    #[allow(clippy::inline_always)]
     #[inline(always)]
-    fn merge(
-        &mut self,
-        major: usize,
-        minor: usize,
-        direction: Direction,
-        set: &mut BTreeSet<(usize, usize)>,
-        generation: Generation,
-    ) -> bool {
-        let (current, next) = Self::pair(major, minor, direction);
-
-        match (self.tiles[current], self.tiles[next]) {
-            (Tile::Number(n, a), Tile::Number(m, b)) => {
-                if (n == m) && (a != generation && b != generation) {
-                    self.score += 1 << (n + 1);
-                    self.max_tile = cmp::max(self.max_tile, n + 1);
-                    self.tiles[current] = Tile::Number(n + 1, generation);
-                    self.tiles[next] = Tile::Empty();
-                    set.insert(next);
-                    self.open_tiles += 1;
-                    return true;
+    fn merge(&mut self, segment: &[(usize, usize)], generation: Generation) -> bool {
+        let run = self.rule.run();
+        let mut merged = false;
+
+        for (i, &current) in segment.iter().enumerate() {
+            let (result, used) = if let Some(power) = segment
+                .get(i..i + run)
+                .and_then(|cells| self.merge_numbers(cells))
+            {
+                (Merged::Tile(Tile::Number(power, generation)), run)
+            } else if let Some(result) = segment
+                .get(i + 1)
+                .and_then(|&next| self.tiles[current].merge(self.tiles[next], generation))
+            {
+                (result, 2)
+            } else {
+                continue;
+            };
+
+            self.apply(current, &segment[i + 1..i + used], result);
+            merged = true;
+        }
+
+        // Close the gaps left by merging
+        if merged {
+            self.collect(segment);
+        }
+        merged
+    }
+
+    // Put the result of a merge in `current`, emptying the other merged cells
+    #[inline]
+    fn apply(&mut self, current: (usize, usize), others: &[(usize, usize)], result: Merged) {
+        for &cell in others {
+            self.tiles[cell] = Tile::Empty();
+            self.open_tiles += 1;
+        }
+        match result {
+            Merged::Tile(tile) => {
+                if let Tile::Number(power, _) = tile {
+                    self.score += self.rule.value(power);
+                    self.max_tile = cmp::max(self.max_tile, power);
                 }
+                self.tiles[current] = tile;
             }
-            (Tile::Empty(), Tile::Number(_, _)) => {
-                set.insert(current);
-                let index = match direction {
-                    Direction::Up | Direction::Left => set.pop_first().unwrap(),
-                    Direction::Down | Direction::Right => set.pop_last().unwrap(),
-                };
-                self.tiles[index] = self.tiles[next];
-                self.tiles[next] = Tile::Empty();
+            Merged::Explosion => self.explode(current),
+        }
+    }
+
+    // Merge across the seam of a collected ring, where the back of the line
+    // meets the front, before merging the rest of the line as usual
+    fn merge_ring(&mut self, line: &[(usize, usize)], generation: Generation) -> bool {
+        let run = self.rule.run();
+        let count = line
+            .iter()
+            .take_while(|&&cell| self.tiles[cell].is_movable())
+            .count();
+
+        // With so few tiles, the seam joins the same tiles as the front
+        if count <= run {
+            return self.merge(line, generation);
+        }
+
+        // The back tiles, followed by the front tile
+        let front = line[0];
+        let mut seam = [(0, 0); MAX_LINE_LENGTH];
+        seam[..run - 1].copy_from_slice(&line[count + 1 - run..count]);
+        seam[run - 1] = front;
+
+        let back = &line[count - 1..count];
+        let seamed = if let Some(power) = self.merge_numbers(&seam[..run]) {
+            Some((
+                Merged::Tile(Tile::Number(power, generation)),
+                &seam[..run - 1],
+            ))
+        } else {
+            self.tiles[front]
+                .merge(self.tiles[back[0]], generation)
+                .map(|result| (result, back))
+        };
+
+        match seamed {
+            Some((result, others)) => {
+                self.apply(front, others, result);
+                self.merge(&line[1..], generation);
+                self.collect(line);
+                true
+            }
+            None => self.merge(line, generation),
+        }
+    }
+
+    // Clear a cell and its movable neighbours, leaving rocks and the frame
+    fn explode(&mut self, cell: (usize, usize)) {
+        for cell in iter::once(cell).chain(self.topology.neighbours(cell)) {
+            if !self.tiles.is_outside(cell) && self.tiles[cell].is_movable() {
+                self.tiles[cell] = Tile::Empty();
+                self.open_tiles += 1;
             }
-            (Tile::Edge(_), _)
-            | (Tile::Corner(_), _)
-            | (_, Tile::Empty())
-            | (_, Tile::Edge(_))
-            | (_, Tile::Corner(_)) => {}
         }
-        false
     }
 
+    #[allow(clippy::inline_always)]
     #[inline(always)]
-    pub fn update(
-        &mut self,
-        direction: Direction,
-        generation: Generation,
-        set: &mut BTreeSet<(usize, usize)>,
-    ) -> bool {
+    pub fn update(&mut self, direction: Direction, generation: Generation) -> bool {
+        let Some((dr, dc)) = self.topology.step(direction) else {
+            return false;
+        };
+        self.spawned.clear();
         let mut moved = false;
+        let mut cells = [(0, 0); MAX_LINE_LENGTH];
 
-        // Iterate in row or column major order. First scan removes slack; second does one merge
-        match direction {
-            Direction::Left | Direction::Up => {
-                const MIDDLE_TILE: usize = 2;
-                for major in NUMBER_TILES_RANGE {
-                    set.clear();
-
-                    for minor in NUMBER_TILES_RANGE {
-                        moved |= self.collect(major, minor, direction, set);
-                    }
-                    for minor in NUMBER_TILES_RANGE {
-                        moved |= self.merge(major, minor, direction, set, generation);
-                    }
-                    // Allow a final middle merge
-                    moved |= self.merge(major, MIDDLE_TILE, direction, set, generation);
-                }
+        // Each line starts from a cell which the move would take off the board
+        for front in self.tiles.coordinates() {
+            let ahead = (
+                front.0.wrapping_add_signed(dr),
+                front.1.wrapping_add_signed(dc),
+            );
+            if self.tiles.is_outside(front) || !self.tiles.is_outside(ahead) {
+                continue;
             }
-            Direction::Right | Direction::Down => {
-                const MIDDLE_TILE: usize = 3;
-                for major in NUMBER_TILES_RANGE.rev() {
-                    set.clear();
-
-                    for minor in NUMBER_TILES_RANGE.rev() {
-                        moved |= self.collect(major, minor, direction, set);
-                    }
-                    for minor in NUMBER_TILES_RANGE.rev() {
-                        moved |= self.merge(major, minor, direction, set, generation);
-                    }
-                    // Allow a final middle merge
-                    moved |= self.merge(major, MIDDLE_TILE, direction, set, generation);
+            let length = self.line(front, (dr, dc), &mut cells);
+            let line = &cells[..length];
+            let rock = line
+                .iter()
+                .position(|&cell| self.tiles[cell] == Tile::Rock());
+
+            moved |= match (self.topology, rock) {
+                (Topology::Flat | Topology::Hex(_) | Topology::Cube, _) => {
+                    self.slide(line, generation)
                 }
-            }
+                // A ring without rocks has no ends, so tiles gather at the front
+                (Topology::Torus, None) => self.collect(line) | self.merge_ring(line, generation),
+                // Otherwise each stretch between rocks slides towards the rock
+                // ahead of it, which may be across the seam
+                (Topology::Torus, Some(rock)) => {
+                    let mut ring = [(0, 0); 2 * MAX_LINE_LENGTH];
+                    ring[..length].copy_from_slice(line);
+                    ring[length..2 * length].copy_from_slice(line);
+                    self.slide(&ring[rock + 1..rock + length], generation)
+                }
+            };
         }
         moved
     }
 
-    // Create a new '2' or '4' number tile in a blank space
+    // Slide and merge cells in order from the front, split where rocks hold tiles apart
+    #[allow(clippy::inline_always)]
+    #[inline(always)]
+    fn slide(&mut self, cells: &[(usize, usize)], generation: Generation) -> bool {
+        let mut moved = false;
+        let mut start = 0;
+        while start < cells.len() {
+            let end = cells[start..]
+                .iter()
+                .position(|&cell| self.tiles[cell] == Tile::Rock())
+                .map_or(cells.len(), |offset| start + offset);
+
+            moved |= self.collect(&cells[start..end]);
+            moved |= self.merge(&cells[start..end], generation);
+            start = end + 1;
+        }
+        moved
+    }
+
+    // Every empty cell, in row-major order
+    fn empty_cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.tiles
+            .coordinates()
+            .filter(|&cell| self.tiles[cell] == Tile::Empty())
+    }
+
+    // Brute force isn't great, but it's an exceptionally small board (about 16 loops maximum)
+    #[inline]
+    fn random_empty(&self, rng: &mut fastrand::Rng) -> (usize, usize) {
+        let insert_index = rng.usize(..self.open_tiles as usize);
+        self.empty_cells()
+            .nth(insert_index)
+            .expect("Failed to find an empty tile")
+    }
+
+    #[inline]
+    fn place(&mut self, cell: (usize, usize), tile: Tile) {
+        self.tiles[cell] = tile;
+        self.open_tiles -= 1;
+        if let Tile::Number(power, _) = tile {
+            self.max_tile = cmp::max(self.max_tile, power);
+        }
+    }
+
+    // Place a spawned tile, reporting it among the cells spawned since the last move
     #[inline]
-    pub fn spawn_tile(&mut self, generation: Generation) -> bool {
+    fn place_spawn(&mut self, cell: (usize, usize), tile: Tile) {
+        self.place(cell, tile);
+        self.spawned.push(cell);
+    }
+
+    // Create a new tile in a blank space, as chosen by the rules
+    #[inline]
+    pub fn spawn_tile(
+        &mut self,
+        generation: Generation,
+        rules: &SpawnRules,
+        rng: &mut fastrand::Rng,
+    ) -> bool {
         if !self.has_space() {
             return false;
         }
 
         // Collect random numbers
-        const CHANCE_OF_FOUR_TILES: u64 = 4;
-        let insert_index = fastrand::u64(..self.open_tiles as u64);
-        let insert_value = if fastrand::u64(..CHANCE_OF_FOUR_TILES) == (CHANCE_OF_FOUR_TILES - 1) {
-            2 // '4' tile
-        } else {
-            1 // '2' tile
+        let cell = self.random_empty(rng);
+        let spawn = rules.pick(rng);
+
+        self.place_spawn(cell, spawn.tile(generation));
+        true
+    }
+
+    // Place a number tile in the given cell, counted from zero at the top left,
+    // or else in the first blank space
+    pub fn spawn_fixed(
+        &mut self,
+        generation: Generation,
+        power: Power,
+        cell: Option<(usize, usize)>,
+    ) -> bool {
+        let start = NUMBER_TILES_RANGE.start;
+        let Some(cell) = cell
+            .map(|(r, c)| (r + start, c + start))
+            .filter(|&cell| self.tiles[cell] == Tile::Empty())
+            .or_else(|| self.empty_cells().next())
+        else {
+            return false;
         };
 
-        let mut cursor = 0;
-
-        // Brute force isn't great, but it's an exceptionally small board (about 16 loops maximum)
-        for r in NUMBER_TILES_RANGE {
-            for c in NUMBER_TILES_RANGE {
-                if (self.tiles[(r, c)]) == Tile::Empty() {
-                    if cursor == insert_index {
-                        self.tiles[(r, c)] = Tile::Number(insert_value, generation);
-                        self.open_tiles -= 1;
-                        self.max_tile = cmp::max(insert_value, self.max_tile);
-                        // Return early
-                        return true;
-                    }
-                    cursor += 1;
-                }
-            }
+        self.place_spawn(cell, Tile::Number(power, generation));
+        true
+    }
+
+    // Place an immovable rock in a blank space
+    pub fn spawn_rock(&mut self, rng: &mut fastrand::Rng) -> bool {
+        if !self.has_space() {
+            return false;
         }
-        unreachable!("Failed to spawn tile");
+
+        let cell = self.random_empty(rng);
+        self.place(cell, Tile::Rock());
+        true
     }
 }
+
+#[test]
+fn update_rock_segments_test() {
+    let (e, r) = (Some(0), None);
+    let mut board = Board::from_rows([
+        [Some(1), Some(1), Some(1), Some(1)],
+        [Some(1), r, Some(1), Some(1)],
+        [e, Some(2), r, Some(2)],
+        [r, Some(1), r, e],
+    ]);
+
+    // Each side of a rock slides separately
+    assert!(board.update(Direction::Left, 1));
+    assert_eq!(
+        board.rows(),
+        [
+            [Some(2), Some(2), e, e],
+            [Some(1), r, Some(2), e],
+            [Some(2), e, r, Some(2)],
+            [r, Some(1), r, e],
+        ]
+    );
+    assert_eq!((board.score, board.open_tiles), (12, 5));
+
+    // Rocks hold tiles in place
+    assert!(board.update(Direction::Up, 2));
+    assert_eq!(
+        board.rows(),
+        [
+            [Some(2), Some(2), Some(2), Some(2)],
+            [Some(1), r, e, e],
+            [Some(2), Some(1), r, e],
+            [r, e, r, e],
+        ]
+    );
+    assert!(!board.update(Direction::Up, 3));
+}
+
+#[test]
+fn update_special_tiles_test() {
+    let e = Some(0);
+    let mut board = Board::from_rows([
+        [Some(3), e, e, e],
+        [Some(2), e, e, e],
+        [e, e, e, e],
+        [e, e, e, e],
+    ]);
+    board.place((1, 2), Tile::Joker());
+    board.place((2, 2), Tile::Double(1));
+    board.place((3, 1), Tile::Double(1));
+    board.place((3, 2), Tile::Double(1));
+
+    // Jokers copy, doublers double, and doublers stack
+    assert!(board.update(Direction::Left, 1));
+    assert!(board.tiles[(1, 1)] == Tile::Number(4, 1));
+    assert!(board.tiles[(2, 1)] == Tile::Number(3, 1));
+    assert!(board.tiles[(3, 1)] == Tile::Double(2));
+    assert_eq!((board.score, board.open_tiles), (16 + 8, 13));
+
+    // Neither stacks nor doubled numbers grow without bound
+    assert!(Tile::Double(16).merge(Tile::Double(1), 1).is_none());
+    assert!(Tile::Double(8).merge(Tile::Number(24, 0), 1).is_none());
+    assert!(Tile::Joker().merge(Tile::Number(30, 0), 1).is_some());
+
+    // A bomb clears its neighbours, except for rocks
+    let mut board = Board::from_rows([
+        [Some(1), Some(3), e, e],
+        [Some(2), None, e, e],
+        [e, e, e, e],
+        [e, e, e, e],
+    ]);
+    board.place((4, 1), Tile::Bomb());
+    assert!(board.update(Direction::Up, 1));
+    assert_eq!(
+        board.rows(),
+        [
+            [e, Some(3), e, e],
+            [e, None, e, e],
+            [e, e, e, e],
+            [e, e, e, e],
+        ]
+    );
+    assert_eq!((board.score, board.open_tiles), (0, 14));
+}
+
+#[test]
+fn update_torus_test() {
+    let (e, r) = (Some(0), None);
+    let mut board = Board {
+        topology: Topology::Torus,
+        ..Board::from_rows([
+            [Some(1), Some(2), Some(2), Some(1)],
+            [e, Some(1), r, Some(1)],
+            [e, e, e, e],
+            [Some(1), e, e, Some(1)],
+        ])
+    };
+
+    // Tiles merge across the seam, and slide around rocks to the far side
+    assert!(board.update(Direction::Left, 1));
+    assert_eq!(
+        board.rows(),
+        [
+            [Some(2), Some(3), e, e],
+            [e, e, r, Some(2)],
+            [e, e, e, e],
+            [Some(2), e, e, e],
+        ]
+    );
+    assert_eq!((board.score, board.open_tiles), (20, 11));
+
+    // Bombs clear neighbours across the seam
+    let mut board = Board {
+        topology: Topology::Torus,
+        ..Board::from_rows([
+            [Some(1), e, e, Some(3)],
+            [e, e, e, e],
+            [e, e, e, e],
+            [e, e, e, Some(2)],
+        ])
+    };
+    board.place((4, 1), Tile::Bomb());
+    assert!(board.update(Direction::Up, 1));
+    assert_eq!(
+        board.rows(),
+        [[e, e, e, Some(2)], [e, e, e, e], [e, e, e, e], [e, e, e, e],]
+    );
+    assert_eq!((board.score, board.open_tiles), (0, 15));
+}
+
+#[test]
+fn update_hex_test() {
+    let mut board = Board::new(&merge::Classic, Topology::Hex(1));
+    assert_eq!(board.open_tiles, 7);
+    board.place((1, 3), Tile::Number(1, 0));
+    board.place((3, 1), Tile::Number(1, 0));
+
+    // Hex boards don't move sideways, but do move along diagonals
+    assert!(!board.update(Direction::Left, 1));
+    assert!(board.update(Direction::UpRight, 1));
+    assert!(board.tiles[(1, 3)] == Tile::Number(2, 1));
+    assert_eq!((board.score, board.open_tiles), (4, 6));
+
+    // A bomb clears neighbours in all six directions
+    board.place((1, 2), Tile::Number(1, 0));
+    board.place((2, 2), Tile::Bomb());
+    board.place((2, 1), Tile::Number(3, 0));
+    assert!(board.update(Direction::Up, 2));
+    assert_eq!((board.score, board.open_tiles), (4, 7));
+}
+
+#[test]
+fn update_cube_test() {
+    let mut board = Board::new(&merge::Classic, Topology::Cube);
+    assert_eq!(board.open_tiles, 64);
+    board.place((1, 1), Tile::Number(1, 0));
+    board.place((1, 16), Tile::Number(1, 0));
+    board.place((4, 9), Tile::Number(2, 0));
+
+    // Tiles move between layers, and stop at the side of their own layer
+    assert!(board.update(Direction::In, 1));
+    assert!(board.tiles[(1, 16)] == Tile::Number(2, 1));
+    assert!(board.update(Direction::Left, 2));
+    assert!(board.tiles[(4, 16)] == Tile::Number(2, 0));
+    assert!(board.tiles[(1, 16)] == Tile::Number(2, 1));
+    assert!(!board.update(Direction::UpLeft, 3));
+    assert_eq!((board.score, board.open_tiles), (4, 62));
+}
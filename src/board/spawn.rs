@@ -0,0 +1,283 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::fmt;
+
+use super::{
+    merge::{Classic, MergeRule},
+    tile::Tile,
+    topology::MAX_CELLS,
+    Generation, Power,
+};
+
+/// Rules for spawning new tiles
+///
+/// Written either as a preset name, or as a table of tile values and
+/// weights followed by optional tiles-per-turn and initial-tile counts.
+/// For example, "2:9,4:1/2/3" spawns 2 nine times in ten, two tiles per
+/// turn, and starts with three tiles. Special tiles are written as
+/// "joker", "x2" (or any other power of two) and "bomb". The "classic"
+/// preset is the merge rule's own table, while other presets are tables
+/// of powers of two.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpawnRules {
+    /// Name shown in records, which parses back to these rules
+    name: String,
+    /// Weight of each spawned tile
+    weights: Vec<(Spawn, u32)>,
+    total: u32,
+    pub per_turn: u32,
+    pub initial: u32,
+}
+
+const PRESETS: [(&str, &str); 3] = [
+    // One in ten is a '4', with the occasional '8'
+    ("hard", "2:88,4:10,8:2/1/2"),
+    ("easy", "2:1/1/2"),
+    // Roughly one tile in sixteen is special
+    ("wild", "2:45,4:15,joker:2,x2:1,bomb:1/1/2"),
+];
+const DEFAULT_PRESET: &str = "classic";
+pub(super) const MAX_SPAWN_POWER: Power = 16;
+
+/// One kind of spawned tile
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Spawn {
+    Number(Power),
+    Joker,
+    Double(Power),
+    Bomb,
+}
+
+impl Spawn {
+    fn parse(text: &str, rule: &dyn MergeRule) -> Option<Self> {
+        let spawnable = |power: Power| (1..=MAX_SPAWN_POWER).contains(&power).then_some(power);
+        // Jokers and doublers add levels, which only double values under the
+        // classic rule
+        let classic = rule.name() == Classic.name();
+
+        match text {
+            "joker" => classic.then_some(Spawn::Joker),
+            "bomb" => Some(Spawn::Bomb),
+            _ => match text.strip_prefix('x') {
+                Some(_) if !classic => None,
+                Some(value) => Classic
+                    .level(value.parse().ok()?)
+                    .and_then(spawnable)
+                    .map(Spawn::Double),
+                None => rule
+                    .level(text.parse().ok()?)
+                    .and_then(spawnable)
+                    .map(Spawn::Number),
+            },
+        }
+    }
+
+    #[inline]
+    pub(super) fn tile(self, generation: Generation) -> Tile {
+        match self {
+            Spawn::Number(power) => Tile::Number(power, generation),
+            Spawn::Joker => Tile::Joker(),
+            Spawn::Double(power) => Tile::Double(power),
+            Spawn::Bomb => Tile::Bomb(),
+        }
+    }
+}
+
+impl Default for SpawnRules {
+    fn default() -> Self {
+        Self::classic(&Classic)
+    }
+}
+
+impl SpawnRules {
+    /// The usual spawns for a merge rule
+    pub fn classic(rule: &dyn MergeRule) -> Self {
+        Self::parse(DEFAULT_PRESET, rule).expect("Malformed spawn preset")
+    }
+
+    /// Parse a preset name or table of the rule's tiles, or return None if malformed
+    pub fn parse(text: &str, rule: &dyn MergeRule) -> Option<Self> {
+        let table = match PRESETS.iter().find(|(name, _)| *name == text) {
+            _ if text == DEFAULT_PRESET => rule.spawn(),
+            Some((_, table)) => table,
+            None => text,
+        };
+        Self::parse_table(table, rule).map(|rules| SpawnRules {
+            name: text.to_string(),
+            ..rules
+        })
+    }
+
+    fn parse_table(text: &str, rule: &dyn MergeRule) -> Option<Self> {
+        let mut parts = text.split('/');
+        let weights = parts
+            .next()?
+            .split(',')
+            .map(|pair| {
+                let (spawn, weight) = pair.split_once(':')?;
+                Some((
+                    Spawn::parse(spawn.trim(), rule)?,
+                    weight.trim().parse().ok()?,
+                ))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let per_turn = parts.next().map_or(Some(1), |count| count.parse().ok())?;
+        let initial = parts.next().map_or(Some(2), |count| count.parse().ok())?;
+
+        let total = weights
+            .iter()
+            .try_fold(0_u32, |total, (_, weight)| total.checked_add(*weight))?;
+        // An empty board never changes, so can't be played
+        let counts = 1..=u32::try_from(MAX_CELLS).ok()?;
+        let valid = counts.contains(&per_turn) && counts.contains(&initial);
+        (total != 0 && valid && parts.next().is_none()).then(|| SpawnRules {
+            name: text.to_string(),
+            weights,
+            total,
+            per_turn,
+            initial,
+        })
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.name == DEFAULT_PRESET
+    }
+
+    /// Every kind of tile which may be spawned
+    pub fn spawns(&self) -> impl Iterator<Item = Spawn> + '_ {
+        self.weights.iter().map(|(spawn, _)| *spawn)
+    }
+
+    /// Kinds of tile with some chance of spawning, leaving out any weighted 0
+    pub fn possible(&self) -> impl Iterator<Item = Spawn> + '_ {
+        self.weights
+            .iter()
+            .filter(|(_, weight)| *weight != 0)
+            .map(|(spawn, _)| *spawn)
+    }
+
+    /// Choose one new tile
+    #[inline]
+    pub fn pick(&self, rng: &mut fastrand::Rng) -> Spawn {
+        let mut choice = rng.u32(..self.total);
+        for &(spawn, weight) in &self.weights {
+            if choice < weight {
+                return spawn;
+            }
+            choice -= weight;
+        }
+        unreachable!("Malformed spawn weights");
+    }
+}
+
+impl fmt::Display for SpawnRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[test]
+fn spawn_rules_parse_test() {
+    use super::merge::Fibonacci;
+
+    let hard = SpawnRules::parse("hard", &Classic).unwrap();
+    assert_eq!(
+        hard.weights,
+        vec![
+            (Spawn::Number(1), 88),
+            (Spawn::Number(2), 10),
+            (Spawn::Number(3), 2)
+        ]
+    );
+    let wild = SpawnRules::parse("joker:1,x4:1,bomb:1", &Classic).unwrap();
+    assert_eq!(
+        wild.spawns().collect::<Vec<_>>(),
+        vec![Spawn::Joker, Spawn::Double(2), Spawn::Bomb]
+    );
+    assert_eq!(SpawnRules::parse(&hard.to_string(), &Classic), Some(hard));
+
+    let custom = SpawnRules::parse("2:9,4:1/2/3", &Classic).unwrap();
+    assert_eq!((custom.per_turn, custom.initial), (2, 3));
+    assert!(SpawnRules::default().is_default());
+
+    // Tables are written in the rule's own values
+    let fibonacci = SpawnRules::classic(&Fibonacci);
+    assert_eq!(
+        fibonacci.weights,
+        vec![(Spawn::Number(1), 3), (Spawn::Number(2), 1)]
+    );
+    assert_eq!(SpawnRules::parse("4:1", &Fibonacci), None);
+
+    // Only bombs are special under other rules
+    assert!(SpawnRules::parse("2:1,bomb:1", &Fibonacci).is_some());
+    for special in ["wild", "2:1,joker:1", "2:1,x2:1"] {
+        assert_eq!(SpawnRules::parse(special, &Fibonacci), None);
+    }
+
+    for malformed in [
+        "3:1",
+        "x3:1",
+        "2:0",
+        "2:1/0",
+        "2:1/1/0",
+        "2:1/65",
+        "2:1/1/2/3",
+        "",
+    ] {
+        assert_eq!(SpawnRules::parse(malformed, &Classic), None);
+    }
+}
+
+/// Immovable rocks, written as the number placed at the start of a game,
+/// optionally followed by the number of moves between further rocks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rocks {
+    pub initial: u32,
+    /// Zero for never
+    pub every: u32,
+}
+
+impl Rocks {
+    pub fn parse(text: &str) -> Option<Self> {
+        let (initial, every) = text.split_once('/').unwrap_or((text, "0"));
+        Some(Rocks {
+            initial: initial.parse().ok()?,
+            every: every.parse().ok()?,
+        })
+    }
+
+    pub fn is_none(self) -> bool {
+        self == Rocks::default()
+    }
+
+    /// Whether a rock appears after this many moves
+    pub fn is_due(self, moves: u32) -> bool {
+        self.every != 0 && moves.is_multiple_of(self.every)
+    }
+}
+
+impl fmt::Display for Rocks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.initial, self.every)
+    }
+}
@@ -24,16 +24,20 @@ pub(super) type Power = u32;
 pub(super) type Generation = u32;
 
 use super::constants::{
-    BOTTOM_EDGE, BOTTOM_LEFT_CORNER, BOTTOM_RIGHT_CORNER, BOTTOM_RIGHT_CORNER_SPACE, EMPTY_TILE,
-    LEFT_EDGE, LEFT_SPACE, RIGHT_EDGE, TOP_EDGE, TOP_LEFT_CORNER, TOP_RIGHT_CORNER,
+    BOMB_TILE, BOTTOM_EDGE, BOTTOM_LEFT_CORNER, BOTTOM_RIGHT_CORNER, BOTTOM_RIGHT_CORNER_SPACE,
+    EMPTY_TILE, JOKER_TILE, LEFT_EDGE, LEFT_SPACE, MAX_SPECIAL_POWER, RIGHT_EDGE, ROCK_TILE,
+    TOP_EDGE, TOP_LEFT_CORNER, TOP_RIGHT_CORNER, WRAPPED_BOTTOM_EDGE, WRAPPED_LEFT_EDGE,
+    WRAPPED_RIGHT_EDGE, WRAPPED_TOP_EDGE,
+};
+use super::{
+    merge::Classic, spawn::MAX_SPAWN_POWER, topology::MAX_GRID_CELLS, MergeRule, Topology,
 };
-use super::BOARD_DIMENSION;
 use crate::colour::Colour;
 use std::fmt;
 use std::ops::{Index, IndexMut};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
-pub(super) enum EdgeSide {
+pub enum EdgeSide {
     Top,
     Bottom,
     Left,
@@ -41,7 +45,7 @@ pub(super) enum EdgeSide {
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
-pub(super) enum CornerSide {
+pub enum CornerSide {
     TopLeft,
     TopRight,
     BottomLeft,
@@ -52,67 +56,251 @@ pub(super) enum CornerSide {
 pub enum Tile {
     Empty(),
     Number(Power, Generation),
+    /// Immovable, and never merges
+    Rock(),
+    /// Merges with any number, raising it one level
+    Joker(),
+    /// Raises the number it merges with by this many levels
+    Double(Power),
+    /// Clears its neighbours when anything merges with it
+    Bomb(),
     Edge(EdgeSide),
     Corner(CornerSide),
+    /// Inside the frame, but not part of the board's shape
+    Void(),
+}
+
+/// What two neighbouring tiles become when they merge
+#[derive(Clone, Copy)]
+pub(super) enum Merged {
+    Tile(Tile),
+    Explosion,
 }
 
-#[derive(Clone)]
-pub struct Tiles(pub [Tile; BOARD_DIMENSION * BOARD_DIMENSION]);
+impl Tile {
+    #[inline]
+    pub(super) fn is_movable(self) -> bool {
+        matches!(
+            self,
+            Self::Number(_, _) | Self::Joker() | Self::Double(_) | Self::Bomb()
+        )
+    }
+
+    /// Whether the tile is outside the board, where lines end
+    #[inline]
+    pub(super) fn is_frame(self) -> bool {
+        matches!(self, Self::Edge(_) | Self::Corner(_) | Self::Void())
+    }
+
+    /// Merge a special tile with the following tile in a line, if the two can
+    /// merge. Number tiles merge with each other under the board's rule
+    #[inline]
+    pub(super) fn merge(self, next: Tile, generation: Generation) -> Option<Merged> {
+        let tile = match (self, next) {
+            (Self::Bomb(), other) | (other, Self::Bomb()) if other.is_movable() => {
+                return Some(Merged::Explosion)
+            }
+            (Self::Number(n, _), Self::Joker()) | (Self::Joker(), Self::Number(n, _)) => {
+                Self::Number(Some(n + 1).filter(|&n| n <= MAX_SPECIAL_POWER)?, generation)
+            }
+            (Self::Number(n, _), Self::Double(k)) | (Self::Double(k), Self::Number(n, _)) => {
+                Self::Number(Some(n + k).filter(|&n| n <= MAX_SPECIAL_POWER)?, generation)
+            }
+            // Doublers stack, up to the largest which can spawn
+            (Self::Double(j), Self::Double(k)) => {
+                Self::Double(Some(j + k).filter(|&k| k <= MAX_SPAWN_POWER)?)
+            }
+            _ => return None,
+        };
+        Some(Merged::Tile(tile))
+    }
+}
+
+/// Cells of a board in row-major order, including its frame
+///
+/// Every board fits the same fixed array, so boards copy without
+/// allocating.
+#[derive(Clone, Copy)]
+pub struct Tiles {
+    cells: [Tile; MAX_GRID_CELLS],
+    len: usize,
+    pub width: usize,
+}
+
+impl Tiles {
+    /// Empty cells of the given size, within a frame
+    pub(super) fn framed(width: usize, height: usize) -> Self {
+        let (bottom, right) = (height + 1, width + 1);
+        let mut tiles = Tiles {
+            cells: [Tile::Void(); MAX_GRID_CELLS],
+            len: (bottom + 1) * (right + 1),
+            width: right + 1,
+        };
+        assert!(tiles.len <= MAX_GRID_CELLS, "Board too large");
+        for (i, tile) in tiles.cells[..tiles.len].iter_mut().enumerate() {
+            *tile = match (i / tiles.width, i % tiles.width) {
+                (0, 0) => Tile::Corner(CornerSide::TopLeft),
+                (0, c) if c == right => Tile::Corner(CornerSide::TopRight),
+                (0, _) => Tile::Edge(EdgeSide::Top),
+                (r, 0) if r == bottom => Tile::Corner(CornerSide::BottomLeft),
+                (r, c) if r == bottom && c == right => Tile::Corner(CornerSide::BottomRight),
+                (r, _) if r == bottom => Tile::Edge(EdgeSide::Bottom),
+                (_, 0) => Tile::Edge(EdgeSide::Left),
+                (_, c) if c == right => Tile::Edge(EdgeSide::Right),
+                _ => Tile::Empty(),
+            };
+        }
+        tiles
+    }
+
+    /// Every cell, including the frame, in row-major order
+    #[inline]
+    pub(super) fn cells(&self) -> &[Tile] {
+        &self.cells[..self.len]
+    }
+
+    /// Whether a cell is beyond the board, which may be past the frame
+    /// after a move between layers
+    #[inline]
+    pub(super) fn is_outside(&self, (r, c): (usize, usize)) -> bool {
+        r >= self.len / self.width || c >= self.width || self[(r, c)].is_frame()
+    }
+
+    /// Every cell, including the frame, in row-major order
+    #[inline]
+    pub(super) fn coordinates(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        (0..self.len).map(move |i| (i / width, i % width))
+    }
+}
 
 impl Index<(usize, usize)> for Tiles {
     type Output = Tile;
 
     fn index(&self, index: (usize, usize)) -> &Self::Output {
-        &(self.0[index.0 * BOARD_DIMENSION + index.1])
+        &(self.cells[index.0 * self.width + index.1])
     }
 }
 
 impl IndexMut<(usize, usize)> for Tiles {
     fn index_mut(&mut self, index: (usize, usize)) -> &mut Self::Output {
-        &mut (self.0[index.0 * BOARD_DIMENSION + index.1])
+        &mut (self.cells[index.0 * self.width + index.1])
     }
 }
 
-impl fmt::Display for Tile {
+/// A value labelled to fit `width`, abbreviating large values to thousands
+/// or millions on narrow tiles
+fn abbreviate(prefix: &str, value: u32, width: usize) -> String {
+    [(1, ""), (1000, "k"), (1_000_000, "M"), (1_000_000_000, "G")]
+        .iter()
+        .map(|(unit, suffix)| format!("{prefix}{}{suffix}", value / unit))
+        .find(|label| label.len() <= width)
+        .unwrap_or_else(|| format!("{prefix}{}G", value / 1_000_000_000))
+}
+
+/// A tile ready for drawing, with the rule which labels its number and
+/// the topology which styles the frame
+pub(super) struct Shown {
+    pub tile: Tile,
+    pub rule: &'static dyn MergeRule,
+    pub topology: Topology,
+    /// Characters across the tile
+    pub width: usize,
+}
+
+impl fmt::Display for Shown {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::Number(num, _) => {
+        let width = self.width;
+        match &self.tile {
+            Tile::Number(num, _) => {
+                let label = match self.rule.label(*num) {
+                    label if label.len() <= width => label,
+                    _ => abbreviate("", self.rule.value(*num), width),
+                };
                 write!(
                     f,
-                    "{}{:^width$}{}",
+                    "{}{label:^width$}{}",
                     Colour::from_power(*num),
-                    u32::pow(2, *num),
                     Colour::default(),
-                    width = BOARD_DIMENSION,
                 )
             }
-            Self::Edge(EdgeSide::Left) => {
+            Tile::Rock() => {
+                write!(
+                    f,
+                    "{}{ROCK_TILE:.width$}{}",
+                    Colour::ROCK,
+                    Colour::default()
+                )
+            }
+            Tile::Joker() => {
+                write!(
+                    f,
+                    "{}{:^width$.width$}{}",
+                    Colour::SPECIAL,
+                    JOKER_TILE.trim(),
+                    Colour::default()
+                )
+            }
+            Tile::Double(power) => {
+                write!(
+                    f,
+                    "{}{:^width$}{}",
+                    Colour::SPECIAL,
+                    abbreviate("x", Classic.value(*power), width),
+                    Colour::default(),
+                )
+            }
+            Tile::Bomb() => {
+                write!(
+                    f,
+                    "{}{:^width$.width$}{}",
+                    Colour::BOMB,
+                    BOMB_TILE.trim(),
+                    Colour::default()
+                )
+            }
+            Tile::Edge(side) if self.topology == Topology::Torus => match side {
+                EdgeSide::Left => write!(f, "{LEFT_SPACE}{WRAPPED_LEFT_EDGE}"),
+                EdgeSide::Right => write!(f, "{WRAPPED_RIGHT_EDGE}"),
+                EdgeSide::Top => write!(f, "{WRAPPED_TOP_EDGE}"),
+                EdgeSide::Bottom => write!(f, "{WRAPPED_BOTTOM_EDGE}"),
+            },
+            Tile::Edge(EdgeSide::Left) => {
                 write!(f, "{LEFT_SPACE}{LEFT_EDGE}")
             }
-            Self::Edge(EdgeSide::Right) => {
+            Tile::Edge(EdgeSide::Right) => {
                 write!(f, "{RIGHT_EDGE}")
             }
-            Self::Edge(EdgeSide::Top) => {
+            Tile::Edge(EdgeSide::Top) => {
                 write!(f, "{TOP_EDGE}")
             }
-            Self::Edge(EdgeSide::Bottom) => {
+            Tile::Edge(EdgeSide::Bottom) => {
                 write!(f, "{BOTTOM_EDGE}")
             }
-            Self::Corner(CornerSide::TopLeft) => {
+            Tile::Corner(CornerSide::TopLeft) => {
                 write!(f, "{LEFT_SPACE}{TOP_LEFT_CORNER}")
             }
-            Self::Corner(CornerSide::TopRight) => {
+            Tile::Corner(CornerSide::TopRight) => {
                 write!(f, "{TOP_RIGHT_CORNER}")
             }
-            Self::Corner(CornerSide::BottomLeft) => {
+            Tile::Corner(CornerSide::BottomLeft) => {
                 write!(f, "{LEFT_SPACE}{BOTTOM_LEFT_CORNER}")
             }
-            Self::Corner(CornerSide::BottomRight) => {
+            Tile::Corner(CornerSide::BottomRight) => {
                 write!(f, "{BOTTOM_RIGHT_CORNER}{BOTTOM_RIGHT_CORNER_SPACE}")
             }
-            Self::Empty() => {
-                write!(f, "{EMPTY_TILE}")
+            Tile::Empty() | Tile::Void() => {
+                write!(f, "{EMPTY_TILE:.width$}")
             }
         }
     }
 }
+
+#[test]
+fn abbreviate_test() {
+    assert_eq!(abbreviate("", 2048, 4), "2048");
+    assert_eq!(abbreviate("", 1_048_576, 4), "1M");
+    assert_eq!(abbreviate("", 131_072, 4), "131k");
+    assert_eq!(abbreviate("x", 1024, 4), "x1k");
+    assert_eq!(abbreviate("x", 65536, 6), "x65536");
+}
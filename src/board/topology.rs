@@ -0,0 +1,271 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::fmt;
+
+use super::constants::{NUMBER_TILES_PER_LINE, NUMBER_TILES_RANGE};
+use super::tile::{Tile, Tiles};
+use super::Direction;
+
+/// Columns from one layer of a cube to the next, across a `Void` column
+pub(super) const LAYER_STRIDE: usize = NUMBER_TILES_PER_LINE + 1;
+
+/// Radius of a hex board, counted in cells out from the centre
+const DEFAULT_HEX_RADIUS: usize = 2;
+pub(crate) const MAX_HEX_RADIUS: usize = 3;
+/// Cells of the largest grid, including its frame, which all boards fit in
+pub(super) const MAX_GRID_CELLS: usize = {
+    let hex = (2 * MAX_HEX_RADIUS + 3) * (2 * MAX_HEX_RADIUS + 3);
+    let cube = (NUMBER_TILES_PER_LINE * LAYER_STRIDE + 1) * (NUMBER_TILES_PER_LINE + 2);
+    if hex > cube {
+        hex
+    } else {
+        cube
+    }
+};
+/// Cells of the largest board, a cube
+pub(super) const MAX_CELLS: usize =
+    NUMBER_TILES_PER_LINE * NUMBER_TILES_PER_LINE * NUMBER_TILES_PER_LINE;
+
+const SQUARE_DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+const CUBE_DIRECTIONS: [Direction; 6] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+    Direction::In,
+    Direction::Out,
+];
+const HEX_DIRECTIONS: [Direction; 6] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::UpLeft,
+    Direction::UpRight,
+    Direction::DownLeft,
+    Direction::DownRight,
+];
+
+/// How the cells of a board connect
+///
+/// The frame of `Edge` and `Corner` tiles is only drawn. Which cells
+/// neighbour each other, and where tiles stop, is decided here.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Topology {
+    /// Tiles stop at the edges
+    #[default]
+    Flat,
+    /// Each row and column is a ring, so tiles leaving one edge enter
+    /// at the other
+    Torus,
+    /// A hexagon of flat-topped cells with this radius, which moves in
+    /// six directions
+    ///
+    /// Cells are stored in axial coordinates: each row of the grid is a
+    /// line running down-right, so the corners of the grid are `Void`.
+    Hex(usize),
+    /// Four layers of four by four, which also moves in and out
+    ///
+    /// Layers are stored side by side, with a `Void` column between each,
+    /// so that moving in or out steps across a whole layer.
+    Cube,
+}
+
+impl Topology {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.split_once(':') {
+            None if text == "flat" => Some(Topology::Flat),
+            None if text == "torus" => Some(Topology::Torus),
+            None if text == "hex" => Some(Topology::Hex(DEFAULT_HEX_RADIUS)),
+            None if text == "cube" => Some(Topology::Cube),
+            Some(("hex", radius)) => radius
+                .parse()
+                .ok()
+                .filter(|radius| (1..=MAX_HEX_RADIUS).contains(radius))
+                .map(Topology::Hex),
+            _ => None,
+        }
+    }
+
+    pub fn is_flat(self) -> bool {
+        self == Topology::Flat
+    }
+
+    /// Whether the board is the usual grid of four by four
+    pub fn is_square(self) -> bool {
+        matches!(self, Topology::Flat | Topology::Torus)
+    }
+
+    /// Name of the board's shape, for telling players which moves it has
+    pub fn shape(self) -> &'static str {
+        match self {
+            Topology::Flat | Topology::Torus => "square",
+            Topology::Hex(_) => "hex",
+            Topology::Cube => "cube",
+        }
+    }
+
+    /// Why `direction` can't be moved in, if it isn't one of `directions`
+    pub fn check(self, direction: Direction) -> Result<(), String> {
+        if self.directions().contains(&direction) {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} isn't a move on {} boards",
+                direction.name(),
+                self.shape()
+            ))
+        }
+    }
+
+    /// Directions in which tiles can move
+    pub fn directions(self) -> &'static [Direction] {
+        match self {
+            Topology::Flat | Topology::Torus => &SQUARE_DIRECTIONS,
+            Topology::Hex(_) => &HEX_DIRECTIONS,
+            Topology::Cube => &CUBE_DIRECTIONS,
+        }
+    }
+
+    /// Number of cells in the board's shape
+    pub fn cells(self) -> usize {
+        match self {
+            Topology::Flat | Topology::Torus => NUMBER_TILES_PER_LINE * NUMBER_TILES_PER_LINE,
+            Topology::Hex(radius) => 3 * radius * (radius + 1) + 1,
+            Topology::Cube => MAX_CELLS,
+        }
+    }
+
+    /// Empty cells of the board's shape, within a frame
+    pub(super) fn tiles(self) -> Tiles {
+        match self {
+            Topology::Flat | Topology::Torus => {
+                Tiles::framed(NUMBER_TILES_PER_LINE, NUMBER_TILES_PER_LINE)
+            }
+            Topology::Hex(radius) => {
+                let mut tiles = Tiles::framed(2 * radius + 1, 2 * radius + 1);
+                for cell in tiles.coordinates() {
+                    if tiles[cell] == Tile::Empty() && Self::hex_offset(radius, cell).is_none() {
+                        tiles[cell] = Tile::Void();
+                    }
+                }
+                tiles
+            }
+            Topology::Cube => {
+                let width = NUMBER_TILES_PER_LINE * LAYER_STRIDE - 1;
+                let mut tiles = Tiles::framed(width, NUMBER_TILES_PER_LINE);
+                for cell in tiles.coordinates() {
+                    if tiles[cell] == Tile::Empty() && cell.1 % LAYER_STRIDE == 0 {
+                        tiles[cell] = Tile::Void();
+                    }
+                }
+                tiles
+            }
+        }
+    }
+
+    /// Axial coordinates of a cell within a hex board, from its centre
+    #[inline]
+    pub(super) fn hex_offset(radius: usize, (r, c): (usize, usize)) -> Option<(isize, isize)> {
+        let centre = radius.cast_signed() + 1;
+        let (r, c) = (r.cast_signed() - centre, c.cast_signed() - centre);
+        ((r + c).unsigned_abs() <= radius).then_some((r, c))
+    }
+
+    /// Change in row and column of a move, or None when the board
+    /// doesn't move that way
+    #[inline]
+    pub(super) fn step(self, direction: Direction) -> Option<(isize, isize)> {
+        match (self, direction) {
+            (_, Direction::Up) => Some((-1, 0)),
+            (_, Direction::Down) => Some((1, 0)),
+            (Topology::Cube, Direction::In) => Some((0, LAYER_STRIDE.cast_signed())),
+            (Topology::Cube, Direction::Out) => Some((0, -LAYER_STRIDE.cast_signed())),
+            // Hex rows run down-right, so sideways in the grid is diagonal on screen
+            (Topology::Flat | Topology::Torus | Topology::Cube, Direction::Left)
+            | (Topology::Hex(_), Direction::UpLeft) => Some((0, -1)),
+            (Topology::Flat | Topology::Torus | Topology::Cube, Direction::Right)
+            | (Topology::Hex(_), Direction::DownRight) => Some((0, 1)),
+            (Topology::Hex(_), Direction::UpRight) => Some((-1, 1)),
+            (Topology::Hex(_), Direction::DownLeft) => Some((1, -1)),
+            _ => None,
+        }
+    }
+
+    /// Cells next to a cell, some of which may be the frame
+    #[inline]
+    pub(super) fn neighbours(self, (r, c): (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+        self.directions().iter().filter_map(move |&direction| {
+            let (dr, dc) = self.step(direction)?;
+            let cell = (r.wrapping_add_signed(dr), c.wrapping_add_signed(dc));
+            Some(match self {
+                Topology::Torus => {
+                    let wrap = |index: usize| {
+                        let start = NUMBER_TILES_RANGE.start;
+                        start + (index + NUMBER_TILES_PER_LINE - start) % NUMBER_TILES_PER_LINE
+                    };
+                    (wrap(cell.0), wrap(cell.1))
+                }
+                Topology::Flat | Topology::Hex(_) | Topology::Cube => cell,
+            })
+        })
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Topology::Flat => write!(f, "flat"),
+            Topology::Torus => write!(f, "torus"),
+            Topology::Hex(radius) => write!(f, "hex:{radius}"),
+            Topology::Cube => write!(f, "cube"),
+        }
+    }
+}
+
+#[test]
+fn topology_parse_test() {
+    assert_eq!(Topology::parse("hex"), Some(Topology::Hex(2)));
+    assert_eq!(Topology::parse("hex:4"), None);
+    for topology in [
+        Topology::Flat,
+        Topology::Torus,
+        Topology::Hex(1),
+        Topology::Cube,
+    ] {
+        assert_eq!(Topology::parse(&topology.to_string()), Some(topology));
+    }
+
+    // A hexagon of radius two has nineteen cells
+    let tiles = Topology::Hex(2).tiles();
+    let count = tiles.cells().iter().filter(|&&tile| tile == Tile::Empty());
+    assert_eq!(count.count(), 19);
+
+    // And a cube has sixty-four
+    let tiles = Topology::Cube.tiles();
+    let count = tiles.cells().iter().filter(|&&tile| tile == Tile::Empty());
+    assert_eq!(count.count(), 64);
+}
@@ -0,0 +1,119 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::fmt;
+
+/// Challenge modes for x800
+///
+/// Written as "timed" for the highest score in three minutes, or
+/// "timed:SECONDS" for another limit; "race" for the fastest time to the
+/// target; or "moves:COUNT" for the highest score in that many moves.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Challenge {
+    #[default]
+    None,
+    /// Seconds until the game ends
+    Timed(u64),
+    Race,
+    /// Moves until the game ends
+    Moves(u32),
+}
+
+const DEFAULT_SECONDS: u64 = 3 * 60;
+const DEFAULT_MOVES: u32 = 200;
+
+/// Tile which a race aims for, unless given a target
+pub const RACE_VALUE: u32 = 512;
+
+impl Challenge {
+    pub fn parse(text: &str) -> Option<Self> {
+        let (name, count) = match text.split_once(':') {
+            Some((name, count)) => (name, Some(count)),
+            None => (text, None),
+        };
+        match (name, count) {
+            ("none", None) => Some(Challenge::None),
+            ("timed", None) => Some(Challenge::Timed(DEFAULT_SECONDS)),
+            ("timed", Some(seconds)) => seconds
+                .parse()
+                .ok()
+                .filter(|&seconds| seconds != 0)
+                .map(Challenge::Timed),
+            ("race", None) => Some(Challenge::Race),
+            ("moves", None) => Some(Challenge::Moves(DEFAULT_MOVES)),
+            ("moves", Some(moves)) => moves
+                .parse()
+                .ok()
+                .filter(|&moves| moves != 0)
+                .map(Challenge::Moves),
+            _ => None,
+        }
+    }
+
+    pub fn is_none(self) -> bool {
+        self == Challenge::None
+    }
+
+    /// Seconds remaining in a timed game
+    #[inline]
+    pub fn time_left(self, elapsed: u64) -> Option<u64> {
+        match self {
+            Challenge::Timed(seconds) => Some(seconds.saturating_sub(elapsed)),
+            _ => None,
+        }
+    }
+
+    /// Moves remaining in a move-limited game
+    #[inline]
+    pub fn moves_left(self, moves: u32) -> Option<u32> {
+        match self {
+            Challenge::Moves(limit) => Some(limit.saturating_sub(moves)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Challenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Challenge::None => write!(f, "none"),
+            Challenge::Timed(seconds) => write!(f, "timed:{seconds}"),
+            Challenge::Race => write!(f, "race"),
+            Challenge::Moves(moves) => write!(f, "moves:{moves}"),
+        }
+    }
+}
+
+#[test]
+fn challenge_parse_test() {
+    assert_eq!(Challenge::parse("timed"), Some(Challenge::Timed(180)));
+    assert_eq!(Challenge::parse("moves:50"), Some(Challenge::Moves(50)));
+    for challenge in [Challenge::None, Challenge::Race, Challenge::Timed(60)] {
+        assert_eq!(Challenge::parse(&challenge.to_string()), Some(challenge));
+    }
+    for malformed in ["timed:0", "race:1", "moves:", "bogus"] {
+        assert_eq!(Challenge::parse(malformed), None);
+    }
+
+    assert_eq!(Challenge::Timed(60).time_left(75), Some(0));
+    assert_eq!(Challenge::Race.moves_left(10), None);
+}
@@ -39,6 +39,14 @@ pub struct Colour {
 }
 
 impl Colour {
+    /// Mid grey, for tiles which aren't numbers
+    pub const ROCK: Colour = Colour { row: 2, column: 14 };
+    /// Bright magenta, for jokers and doublers
+    pub const SPECIAL: Colour = Colour { row: 5, column: 5 };
+    /// Bright red
+    pub const BOMB: Colour = Colour { row: 5, column: 0 };
+
+    #[allow(clippy::inline_always)]
     #[inline(always)]
     pub const fn from_power(power: Power) -> Self {
         match power {
@@ -82,8 +90,8 @@ fn power_to_colour_zero_test() {
 fn power_to_colour_cycle_test() {
     for power in 0..=255_u8 {
         assert_eq!(
-            Colour::from_power(power as Power),
-            Colour::from_power(power as Power % 13)
+            Colour::from_power(Power::from(power)),
+            Colour::from_power(Power::from(power) % 13)
         );
     }
 }
@@ -93,10 +101,7 @@ impl fmt::Display for Colour {
         let (bg, fg) = if *self == Self::default() {
             (0_u32, 15_u32)
         } else {
-            (
-                16_u32 + self.column as u32 + (self.row as u32 * 36_u32),
-                0_u32,
-            )
+            (16_u32 + self.column + (self.row * 36_u32), 0_u32)
         };
 
         write!(f, "\u{1B}[48;5;{bg}m\u{1B}[38;5;{fg}m")
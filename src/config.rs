@@ -0,0 +1,301 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use crate::board::{
+    constants::{MAX_HEX_RADIUS, MAX_WIN_POWER},
+    merge::{self, Classic},
+    MergeRule, Power, Rocks, SpawnRules, Topology,
+};
+use crate::challenge::{self, Challenge};
+use crate::date::Date;
+use crate::net;
+use crate::puzzle::Puzzle;
+use crate::status::Layout;
+
+/// Command-line configuration for x800
+pub const USAGE: &str = "usage: x800 [--status=LAYOUT] [--seed=SEED] [--target=TILE]
+                   [--spawn=RULES] [--evil] [--merge=RULE]
+                   [--rocks=COUNT[/EVERY]] [--topology=SHAPE]
+                   [--challenge=MODE] [--puzzle=NAME] [--machine]
+       x800 daily
+       x800 versus
+       x800 host [--port=PORT]
+       x800 join ADDRESS [--port=PORT]
+       x800 watch ADDRESS
+       x800 serve [--port=PORT]
+       x800 puzzles
+       x800 scores
+       x800 stats
+
+  --status=LAYOUT  fields shown beneath the board, separated by ','
+                   within a line and '/' between lines. Fields are:
+                   score, moves, best, record, time, seed, keys
+  --seed=SEED      hexadecimal seed, to replay a game
+  --target=TILE    tile which wins the game, eg. 512 or 4096. By
+                   default, the first tile worth 2048 or more
+  --spawn=RULES    new tiles: classic, hard, easy or wild. Or a table
+                   of tiles and weights, then optional tiles per turn
+                   and initial tiles, eg. 2:9,4:1/1/2. Special tiles
+                   are joker, x2 and bomb. Only bombs spawn under
+                   other merge rules
+  --evil           place each new tile where it hurts most
+  --merge=RULE     how tiles merge: classic, fibonacci, triples
+                   (three equal powers of three) or threes (1+2)
+  --rocks=N[/K]    start with N immovable rocks, adding another
+                   every K moves
+  --topology=SHAPE flat, or torus where tiles leaving one edge
+                   enter at the opposite edge. Or hex, a hexagon
+                   moved with qweasd, or hex:R with radius 1 to 3.
+                   Or cube, four layers moved between with q and e
+  --challenge=MODE timed: highest score in 3 minutes, or timed:SECS.
+                   race: fastest to the target, 512 by default.
+                   moves: highest score in 200 moves, or moves:N
+  --puzzle=NAME    play a bundled puzzle, or the first in a file
+  --port=PORT      port to host or serve on, or to join when the
+                   address has none. 2048 by default
+  --machine        play by JSON lines on standard input and output,
+                   as {\"move\":\"left\"} or {\"undo\":true}, for bots
+  --publish=ADDR   let others watch the game at ADDR: a local port,
+                   a TCP address, or the path of a Unix socket. A
+                   tcp: or unix: prefix chooses between them
+
+  daily            play today's shared game, or show its summary
+  versus           race a second player on the same keyboard, who
+                   moves with ijkl or the arrow keys
+  host             race an opponent over the network, who joins and
+                   plays by the options given here
+  join ADDRESS     race the player hosting at ADDRESS
+  watch ADDRESS    watch a game published at ADDRESS
+  serve            serve separate games, with the options given, to
+                   everyone connecting with telnet or nc
+  puzzles          list the bundled puzzles, and which are solved
+  scores           list the high-score table
+  stats            show lifetime statistics
+";
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Command {
+    #[default]
+    Play,
+    Daily,
+    Versus,
+    Host,
+    Join,
+    Watch,
+    Serve,
+    Machine,
+    Puzzles,
+    Scores,
+    Stats,
+}
+
+#[derive(Clone)]
+pub struct Config {
+    pub command: Command,
+    pub layout: Layout,
+    pub seed: Option<u64>,
+    /// Power of the tile which wins the game
+    pub target: Power,
+    pub spawn: SpawnRules,
+    pub evil: bool,
+    pub rocks: Rocks,
+    pub merge: &'static dyn MergeRule,
+    pub topology: Topology,
+    pub challenge: Challenge,
+    /// Date of the daily game being played
+    pub daily: Option<Date>,
+    pub puzzle: Option<Puzzle>,
+    /// Port of a networked race
+    pub port: u16,
+    /// Host of a networked race when joining one, or of a game to watch
+    pub address: Option<String>,
+    /// Where spectators can watch the game
+    pub publish: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            command: Command::default(),
+            layout: Layout::default(),
+            seed: None,
+            target: Classic.target(),
+            spawn: SpawnRules::default(),
+            evil: false,
+            rocks: Rocks::default(),
+            merge: &Classic,
+            topology: Topology::Flat,
+            challenge: Challenge::None,
+            daily: None,
+            puzzle: None,
+            port: net::DEFAULT_PORT,
+            address: None,
+            publish: None,
+        }
+    }
+}
+
+impl Config {
+    /// Parse arguments, not including the program name
+    ///
+    /// # Errors
+    ///
+    /// Returns a message describing the first unrecognised argument
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut config = Config::default();
+        // Tile values depend on the merge rule, so are read last
+        let (mut target, mut spawn) = (None, None);
+
+        while let Some(arg) = args.next() {
+            let (name, value) = arg.split_once('=').unwrap_or((&arg, ""));
+            match name {
+                "--status" => {
+                    config.layout =
+                        Layout::parse(value).ok_or(format!("unknown status layout '{value}'"))?;
+                }
+                "--seed" => {
+                    config.seed = Some(
+                        u64::from_str_radix(value, 16)
+                            .map_err(|_| format!("malformed seed '{value}'"))?,
+                    );
+                }
+                "--target" => target = Some(value.to_string()),
+                "--spawn" => spawn = Some(value.to_string()),
+                "--merge" => {
+                    config.merge =
+                        merge::find(value).ok_or(format!("unknown merge rule '{value}'"))?;
+                }
+                "--topology" => {
+                    config.topology =
+                        Topology::parse(value).ok_or(match value.strip_prefix("hex:") {
+                            Some(_) => format!("hex radius must be from 1 to {MAX_HEX_RADIUS}"),
+                            None => format!("unknown topology '{value}'"),
+                        })?;
+                }
+                "--evil" => config.evil = true,
+                "--rocks" => {
+                    config.rocks =
+                        Rocks::parse(value).ok_or(format!("malformed rocks '{value}'"))?;
+                }
+                "--challenge" => {
+                    config.challenge =
+                        Challenge::parse(value).ok_or(format!("unknown challenge '{value}'"))?;
+                }
+                "--puzzle" => config.puzzle = Some(Puzzle::find(value)?),
+                "--publish" => config.publish = Some(value.to_string()),
+                "--machine" => config.command = Command::Machine,
+                "--port" => {
+                    config.port = value
+                        .parse()
+                        .map_err(|_| format!("malformed port '{value}'"))?;
+                }
+                "daily" => config.command = Command::Daily,
+                "versus" => config.command = Command::Versus,
+                "host" => config.command = Command::Host,
+                "join" => {
+                    config.command = Command::Join;
+                    config.address = Some(args.next().ok_or("join needs an address")?);
+                }
+                "serve" => config.command = Command::Serve,
+                "watch" => {
+                    config.command = Command::Watch;
+                    config.address = Some(args.next().ok_or("watch needs an address")?);
+                }
+                "puzzles" => config.command = Command::Puzzles,
+                "scores" => config.command = Command::Scores,
+                "stats" => config.command = Command::Stats,
+                _ => return Err(format!("unknown argument '{arg}'")),
+            }
+        }
+
+        // Head-to-head games are always races, on a square board
+        if matches!(config.command, Command::Versus | Command::Host) {
+            if !matches!(config.challenge, Challenge::None | Challenge::Race) {
+                return Err("head-to-head games are always races".to_string());
+            }
+            if !config.topology.is_square() {
+                return Err(format!("races can't be played on a {}", config.topology));
+            }
+            if config.puzzle.is_some() {
+                return Err("puzzles can't be raced".to_string());
+            }
+            config.challenge = Challenge::Race;
+        }
+
+        let rule = config.merge;
+        config.target = match target {
+            Some(value) => value
+                .parse()
+                .ok()
+                .and_then(|tile| rule.level(tile))
+                .filter(|power| (3..=MAX_WIN_POWER).contains(power))
+                .ok_or(format!("target '{value}' is not a {} tile", rule.name()))?,
+            None if config.challenge == Challenge::Race => {
+                rule.level_at_least(challenge::RACE_VALUE)
+            }
+            None => rule.target(),
+        };
+        config.spawn = match spawn {
+            Some(value) => {
+                SpawnRules::parse(&value, rule).ok_or(format!("malformed spawn rules '{value}'"))?
+            }
+            None => SpawnRules::classic(rule),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Refuse options which can't be used together
+    fn validate(&self) -> Result<(), String> {
+        // Each session would publish to the same address
+        if self.command == Command::Serve && self.publish.is_some() {
+            return Err("served games can't be published".to_string());
+        }
+        // Puzzles bring their own board, spawns and goal
+        if self.puzzle.is_some() {
+            let conflicts = [
+                ("--challenge", !self.challenge.is_none()),
+                ("--evil", self.evil),
+                ("--rocks", !self.rocks.is_none()),
+                ("--spawn", !self.spawn.is_default()),
+                ("--merge", self.merge.name() != Classic.name()),
+                ("--topology", !self.topology.is_flat()),
+                ("--target", self.target != self.merge.target()),
+            ];
+            if let Some((option, _)) = conflicts.iter().find(|(_, conflict)| *conflict) {
+                return Err(format!("puzzles can't be played with {option}"));
+            }
+        }
+        let fits =
+            |count: u32| usize::try_from(count).is_ok_and(|count| count <= self.topology.cells());
+        if !fits(self.spawn.initial.max(self.spawn.per_turn)) {
+            return Err(format!(
+                "spawn rules '{}' place more tiles than the board holds",
+                self.spawn
+            ));
+        }
+        if !fits(self.spawn.initial.saturating_add(self.rocks.initial)) {
+            return Err("rocks and starting tiles don't fit on the board".to_string());
+        }
+        Ok(())
+    }
+}
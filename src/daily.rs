@@ -0,0 +1,239 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::{
+    fmt::{self, Write},
+    io,
+};
+
+use crate::{
+    board::{
+        constants::{GAME_FAILURE_MESSAGE, NUMBER_TILES_PER_LINE},
+        Rows,
+    },
+    config::Config,
+    date::Date,
+    game::Game,
+    play::play,
+    store,
+    terminal::Tty,
+};
+
+/// Daily challenge for x800
+///
+/// Everyone playing on the same UTC date gets the same seed, and so the
+/// same tiles. The first finished game of each day is kept as a line of
+/// `key=value` pairs, and can be shared as a short summary.
+const FILE_NAME: &str = "daily";
+const ROW_SEPARATOR: char = '/';
+const ROCK_CELL: char = '#';
+
+// Empty cells, then one square for each pair of powers from '2'
+const SQUARES: [&str; 7] = ["⬛", "⬜", "🟦", "🟩", "🟨", "🟧", "🟥"];
+const ROCK_SQUARE: &str = "🟫";
+
+/// Seed for a date, mixed so that neighbouring days are unrelated
+pub fn seed(date: Date) -> u64 {
+    let days = date.year.unsigned_abs() * 10_000 + u64::from(date.month) * 100;
+    let mut z = (days + u64::from(date.day)).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Daily {
+    pub date: Date,
+    pub score: u32,
+    /// Value of the largest tile
+    pub tile: u32,
+    pub moves: u32,
+    /// The final board
+    pub grid: Rows,
+}
+
+impl Daily {
+    /// The result of a game, if it was the daily game
+    pub fn from_game(game: &Game) -> Option<Self> {
+        let date = game.daily.filter(|&date| game.seed == seed(date))?;
+        Some(Daily {
+            date,
+            score: game.board.score(),
+            tile: game.board.rule().value(game.board.max_tile()),
+            moves: game.moves,
+            grid: game.board.rows(),
+        })
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut daily = Daily {
+            date: Date::from_days(0),
+            score: 0,
+            tile: 0,
+            moves: 0,
+            grid: [[Some(0); NUMBER_TILES_PER_LINE]; NUMBER_TILES_PER_LINE],
+        };
+
+        for pair in line.split_whitespace() {
+            let (key, value) = pair.split_once('=')?;
+            match key {
+                "date" => daily.date = Date::parse(value)?,
+                "score" => daily.score = value.parse().ok()?,
+                "tile" => daily.tile = value.parse().ok()?,
+                "moves" => daily.moves = value.parse().ok()?,
+                "grid" => daily.grid = parse_grid(value)?,
+                _ => {}
+            }
+        }
+        (daily.moves != 0).then_some(daily)
+    }
+
+    /// The kept result for a date, if there is one
+    pub fn load(date: Date) -> io::Result<Option<Self>> {
+        let text = store::read(FILE_NAME)?;
+        Ok(text
+            .lines()
+            .filter_map(Self::parse)
+            .find(|daily| daily.date == date))
+    }
+
+    /// Keep this result, unless the day already has one
+    pub fn record(&self) -> io::Result<()> {
+        store::update(FILE_NAME, |text| {
+            let mut text = text.to_string();
+            if !text
+                .lines()
+                .filter_map(Self::parse)
+                .any(|daily| daily.date == self.date)
+            {
+                text.push_str(&self.to_string());
+                text.push('\n');
+            }
+            text
+        })
+    }
+
+    /// Summary to share, with the final board as coloured squares
+    pub fn share(&self, buffer: &mut String) -> fmt::Result {
+        writeln!(buffer, "x800 daily {}", self.date)?;
+        writeln!(
+            buffer,
+            "score {}  tile {}  moves {}",
+            self.score, self.tile, self.moves
+        )?;
+        for row in self.grid {
+            for cell in row {
+                buffer.push_str(match cell {
+                    Some(power) => SQUARES[power.div_ceil(2).min(6) as usize],
+                    None => ROCK_SQUARE,
+                });
+            }
+            buffer.push('\n');
+        }
+        Ok(())
+    }
+}
+
+fn parse_grid(text: &str) -> Option<Rows> {
+    let mut grid = [[Some(0); NUMBER_TILES_PER_LINE]; NUMBER_TILES_PER_LINE];
+    let mut rows = text.split(ROW_SEPARATOR);
+    for row in &mut grid {
+        let mut cells = rows.next()?.chars();
+        for cell in row {
+            *cell = match cells.next()? {
+                ROCK_CELL => None,
+                digit => Some(digit.to_digit(36)?),
+            };
+        }
+    }
+    rows.next().is_none().then_some(grid)
+}
+
+impl fmt::Display for Daily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "date={} score={} tile={} moves={} grid=",
+            self.date, self.score, self.tile, self.moves
+        )?;
+        for (i, row) in self.grid.iter().enumerate() {
+            if i != 0 {
+                f.write_char(ROW_SEPARATOR)?;
+            }
+            for cell in row {
+                f.write_char(match cell {
+                    Some(power) => char::from_digit(*power, 36).unwrap_or(ROCK_CELL),
+                    None => ROCK_CELL,
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Play today's game, unless it's already been played, then show the summary
+///
+/// # Errors
+///
+/// Returns an error if the game fails, or if stored data can't be read
+pub fn run(config: &Config) -> io::Result<()> {
+    let date = Date::today();
+    let mut daily = Daily::load(date)?;
+
+    if daily.is_none() {
+        // Everyone plays by the default rules
+        let config = Config {
+            layout: config.layout.clone(),
+            seed: Some(seed(date)),
+            daily: Some(date),
+            ..Config::default()
+        };
+        play(&mut Tty::open(), Box::new(io::stdout()), &config)?;
+        daily = Daily::load(date)?;
+    }
+
+    if let Some(daily) = daily {
+        let mut summary = String::new();
+        daily
+            .share(&mut summary)
+            .map_err(|_| io::Error::other(GAME_FAILURE_MESSAGE))?;
+        print!("{summary}");
+    }
+    Ok(())
+}
+
+#[test]
+fn daily_round_trip_test() {
+    let line = "date=2026-10-18 score=3000 tile=256 moves=250 grid=1234/5678/9abc/#000";
+    let daily = Daily::parse(line).unwrap();
+    assert_eq!(daily.to_string(), line);
+    assert_eq!(daily.grid[3], [None, Some(0), Some(0), Some(0)]);
+
+    let mut summary = String::new();
+    daily.share(&mut summary).unwrap();
+    assert!(summary.ends_with("🟧🟧🟥🟥\n🟫⬛⬛⬛\n"));
+
+    // Each day has its own seed
+    let today = Date::parse("2026-10-18").unwrap();
+    assert_eq!(seed(today), seed(today));
+    assert_ne!(seed(today), seed(Date::parse("2026-10-19").unwrap()));
+}
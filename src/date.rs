@@ -0,0 +1,92 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::{fmt, time};
+
+/// Calendar dates in UTC, without a dependency on a date library
+///
+/// Conversion follows Howard Hinnant's `civil_from_days`. See:
+/// <https://howardhinnant.github.io/date_algorithms.html>
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+}
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+impl Date {
+    pub fn today() -> Self {
+        let seconds = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        Self::from_days(i64::try_from(seconds / SECONDS_PER_DAY).unwrap_or(0))
+    }
+
+    /// Date from the number of days since 1970-01-01
+    pub const fn from_days(days: i64) -> Self {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        // Both are small and positive by construction
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Date {
+            year,
+            month: month as u32,
+            day: day as u32,
+        }
+    }
+
+    /// Parse a date written as YYYY-MM-DD
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.trim().splitn(3, '-');
+        let date = Date {
+            year: parts.next()?.parse().ok()?,
+            month: parts.next()?.parse().ok()?,
+            day: parts.next()?.parse().ok()?,
+        };
+        ((1..=12).contains(&date.month) && (1..=31).contains(&date.day)).then_some(date)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[test]
+fn date_from_days_test() {
+    assert_eq!(Date::from_days(0).to_string(), "1970-01-01");
+    assert_eq!(Date::from_days(11_016).to_string(), "2000-02-29");
+    assert_eq!(Date::from_days(20_744).to_string(), "2026-10-18");
+    assert_eq!(Date::parse("2026-10-18"), Some(Date::from_days(20_744)));
+    assert_eq!(Date::parse("2026-13-01"), None);
+}
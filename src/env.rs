@@ -0,0 +1,326 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use crate::board::{Action, Direction, Tile};
+use crate::config::Config;
+use crate::game::{Best, Game, Phase, Step};
+
+// Mixed into a rollout's seed for its policy, so that the moves chosen are
+// independent of the tiles drawn
+const POLICY_STREAM: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Weights of the parts of each step's reward
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shaping {
+    /// Per point scored by merges
+    pub score: f64,
+    /// Per empty cell after the move
+    pub empty: f64,
+    /// Per doubling of the highest tile's value
+    pub max_tile: f64,
+    /// For an action which changes nothing
+    pub invalid: f64,
+}
+
+impl Default for Shaping {
+    fn default() -> Self {
+        Shaping {
+            score: 1.0,
+            empty: 0.0,
+            max_tile: 0.0,
+            invalid: 0.0,
+        }
+    }
+}
+
+/// What an agent sees of the game
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Observation {
+    /// Cells in row-major order. Numbered tiles are their level, where a
+    /// classic 2 is 1, and other cells are `EMPTY`, `SPECIAL` or `BLOCKED`
+    pub cells: Vec<u32>,
+    pub width: usize,
+    pub height: usize,
+    /// Whether each of `Env::actions` would change the board
+    pub mask: Vec<bool>,
+}
+
+impl Observation {
+    /// Cell holding nothing
+    pub const EMPTY: u32 = 0;
+    /// Cell holding a joker, bomb, or doubler
+    pub const SPECIAL: u32 = u32::MAX - 1;
+    /// Cell holding a rock, or outside the board's shape
+    pub const BLOCKED: u32 = u32::MAX;
+
+    #[inline]
+    pub(crate) fn level(tile: Tile) -> u32 {
+        match tile {
+            Tile::Empty() => Observation::EMPTY,
+            Tile::Number(power, _) => power,
+            Tile::Joker() | Tile::Bomb() | Tile::Double(_) => Observation::SPECIAL,
+            Tile::Rock() | Tile::Edge(_) | Tile::Corner(_) | Tile::Void() => Observation::BLOCKED,
+        }
+    }
+}
+
+/// Details of a step, besides its reward
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Info {
+    pub score: u32,
+    pub moves: u32,
+    /// Value of the highest tile
+    pub max_tile: u32,
+    /// Whether the action changed the board
+    pub changed: bool,
+    pub won: bool,
+}
+
+/// How `Env::rollout` chooses each move
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Policy {
+    /// Any legal move, at random
+    Random,
+    /// The legal move with the highest reward, the first of any ties
+    Greedy,
+}
+
+/// A game of x800 for reinforcement learning, stepped one action at a time
+///
+/// Actions index into `actions`. The game keeps going past the target,
+/// except in races and puzzles, which end there. Games played this way are
+/// not recorded.
+#[derive(Clone)]
+pub struct Env {
+    config: Config,
+    shaping: Shaping,
+    pub(crate) game: Game,
+    /// Whether each of `actions` would change the board, found once per move
+    mask: Vec<bool>,
+}
+
+impl Env {
+    /// An environment for games chosen by `config`, started from its seed
+    #[must_use]
+    pub fn new(config: &Config, shaping: Shaping) -> Self {
+        let seed = config.seed.unwrap_or_else(|| fastrand::u64(..));
+        let mut env = Env {
+            config: config.clone(),
+            shaping,
+            game: Game::new(seed, config, Best::default()),
+            mask: Vec::new(),
+        };
+        env.update_mask();
+        env
+    }
+
+    /// Moves of the board's topology, in the order of actions
+    #[must_use]
+    pub fn actions(&self) -> &'static [Direction] {
+        self.game.board.topology().directions()
+    }
+
+    /// Start a new game with tiles drawn from `seed`
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.game = Game::new(seed, &self.config, Best::default());
+        self.update_mask();
+        self.observe()
+    }
+
+    /// Make one move, returning what follows, its reward, whether the game
+    /// is over, and the details
+    ///
+    /// # Panics
+    ///
+    /// Panics if `action` is not an index into `actions`
+    pub fn step(&mut self, action: usize) -> (Observation, f64, bool, Info) {
+        let direction = self.actions()[action];
+        // Masked actions are refused here, since a move which changes nothing
+        // on a full board would end the game
+        if !self.mask[action] {
+            return (
+                self.observe(),
+                self.shaping.invalid,
+                self.game.over,
+                self.info(),
+            );
+        }
+
+        let (score, max_tile) = (self.game.board.score(), max_tile(&self.game));
+        let step = self.game.act(Action::Direction(direction));
+        // Agents keep going past the target, without a prompt
+        if self.game.phase == Phase::Won {
+            self.game.phase = Phase::KeepGoing;
+        }
+        self.update_mask();
+        if step == Step::Over || !self.mask.contains(&true) {
+            self.game.over = true;
+            self.mask.fill(false);
+        }
+
+        let reward = self.reward(score, max_tile, &self.game);
+        let info = Info {
+            changed: true,
+            ..self.info()
+        };
+        (self.observe(), reward, self.game.over, info)
+    }
+
+    /// Reward for a move to `game`, from a board with `score` and the
+    /// highest tile `max_tile`
+    fn reward(&self, score: u32, max_tile: f64, game: &Game) -> f64 {
+        let empty = (game.board.grid().flatten())
+            .filter(|&&tile| Observation::level(tile) == Observation::EMPTY);
+        let empty = u32::try_from(empty.count()).unwrap_or(u32::MAX);
+        self.shaping.score * f64::from(game.board.score() - score)
+            + self.shaping.empty * f64::from(empty)
+            + self.shaping.max_tile * (self::max_tile(game) - max_tile)
+    }
+
+    /// The game as it stands
+    #[must_use]
+    pub fn observe(&self) -> Observation {
+        let mut observation = Observation {
+            cells: Vec::new(),
+            width: 0,
+            height: 0,
+            mask: self.mask.clone(),
+        };
+        for row in self.game.board.grid() {
+            observation.width = row.len();
+            observation.height += 1;
+            observation
+                .cells
+                .extend(row.iter().copied().map(Observation::level));
+        }
+        observation
+    }
+
+    fn update_mask(&mut self) {
+        let game = &self.game;
+        self.mask = (self.actions().iter())
+            .map(|&direction| !game.over && game.board.can_move(direction, game.generation + 1))
+            .collect();
+    }
+
+    #[must_use]
+    pub fn info(&self) -> Info {
+        let board = &self.game.board;
+        Info {
+            score: board.score(),
+            moves: self.game.moves,
+            max_tile: board.rule().value(board.max_tile()),
+            changed: false,
+            won: self.game.has_won(),
+        }
+    }
+
+    /// Play a game from `seed` to its end, or until `max_moves` moves, since
+    /// some spawn rules never run out of moves. Returns the final details
+    pub fn rollout(&mut self, seed: u64, policy: Policy, max_moves: u32) -> Info {
+        let mut observation = self.reset(seed);
+        let mut rng = fastrand::Rng::with_seed(seed ^ POLICY_STREAM);
+        while self.game.moves < max_moves {
+            let legal = (observation.mask.iter().enumerate())
+                .filter_map(|(action, &legal)| legal.then_some(action));
+            let action = match policy {
+                Policy::Random => {
+                    let legal: Vec<usize> = legal.collect();
+                    rng.choice(legal)
+                }
+                Policy::Greedy => legal
+                    .map(|action| (action, self.try_action(action)))
+                    .reduce(|best, next| if next.1 > best.1 { next } else { best })
+                    .map(|(action, _)| action),
+            };
+            let Some(action) = action else {
+                return self.info();
+            };
+            let (next, _, done, info) = self.step(action);
+            if done {
+                return info;
+            }
+            observation = next;
+        }
+        self.info()
+    }
+
+    /// Reward a legal action would earn, without taking it
+    fn try_action(&self, action: usize) -> f64 {
+        let mut game = self.game.clone();
+        game.act(Action::Direction(self.actions()[action]));
+        self.reward(self.game.board.score(), max_tile(&self.game), &game)
+    }
+}
+
+/// Base-two logarithm of the highest tile's value
+fn max_tile(game: &Game) -> f64 {
+    let board = &game.board;
+    f64::from(board.rule().value(board.max_tile()).max(1)).log2()
+}
+
+#[test]
+fn env_test() {
+    let config = Config::default();
+    let mut env = Env::new(&config, Shaping::default());
+    let start = env.reset(1);
+    assert_eq!(start, env.reset(1));
+    assert_eq!((start.width, start.height), (4, 4));
+    assert_eq!(start.mask.len(), env.actions().len());
+
+    // Masked actions change nothing, and legal ones score their merges
+    let mut done = false;
+    let mut total = 0.0;
+    while !done {
+        let observation = env.observe();
+        let masked = observation.mask.iter().position(|&legal| !legal);
+        if let Some(action) = masked {
+            let (after, reward, _, info) = env.step(action);
+            assert_eq!((after, reward, info.changed), (observation, 0.0, false));
+        }
+        let action = env.observe().mask.iter().position(|&legal| legal).unwrap();
+        let (_, reward, over, info) = env.step(action);
+        assert!(info.changed);
+        total += reward;
+        done = over;
+    }
+    assert!(env.observe().mask.iter().all(|&legal| !legal));
+    assert!((total - f64::from(env.info().score)).abs() < 1e-9);
+
+    // Rollouts play to the end, the same way from the same seed
+    for policy in [Policy::Random, Policy::Greedy] {
+        let info = env.rollout(3, policy, u32::MAX);
+        assert!(env.game.over && info.moves > 0);
+        assert_eq!(info, env.rollout(3, policy, u32::MAX));
+    }
+
+    // Bombs explode against anything, so only the move limit ends these
+    let args = ["--spawn=bomb:1"].map(String::from);
+    let mut env = Env::new(
+        &Config::from_args(args.into_iter()).unwrap(),
+        Shaping::default(),
+    );
+    for policy in [Policy::Random, Policy::Greedy] {
+        assert_eq!(env.rollout(1, policy, 500).moves, 500);
+        assert!(!env.game.over);
+    }
+}
@@ -0,0 +1,286 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::{
+    ffi::{c_char, c_int, CStr},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use crate::board::Direction;
+use crate::config::Config;
+use crate::env::{Env, Observation, Shaping};
+use crate::machine;
+
+// Version of the interface, as in x800.h
+const ABI_VERSION: u32 = 1;
+
+// Results of `x800_move`, as in x800.h
+const ERROR: c_int = -1;
+const UNCHANGED: c_int = 0;
+const CHANGED: c_int = 1;
+const OVER: c_int = 2;
+
+/// Run `body`, returning `failed` if it panics rather than unwinding into C
+fn guard<T>(failed: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(failed)
+}
+
+/// Version of the interface the library was built with
+#[no_mangle]
+pub extern "C" fn x800_abi_version() -> u32 {
+    ABI_VERSION
+}
+
+/// Create a game from command-line `options`, which may be null, with tiles
+/// drawn from `seed`. Returns null if the options are invalid
+///
+/// # Safety
+///
+/// `options` must be null or a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn x800_new(options: *const c_char, seed: u64) -> *mut Env {
+    guard(ptr::null_mut(), || {
+        let mut config = if options.is_null() {
+            Config::default()
+        } else {
+            let Ok(options) = CStr::from_ptr(options).to_str() else {
+                return ptr::null_mut();
+            };
+            match Config::from_args(options.split_whitespace().map(String::from)) {
+                Ok(config) => config,
+                Err(_) => return ptr::null_mut(),
+            }
+        };
+        config.seed = Some(seed);
+        Box::into_raw(Box::new(Env::new(&config, Shaping::default())))
+    })
+}
+
+/// Free a game. Null is ignored
+///
+/// # Safety
+///
+/// `game` must be null or come from `x800_new`, and not be used again
+#[no_mangle]
+pub unsafe extern "C" fn x800_free(game: *mut Env) {
+    guard((), || {
+        if !game.is_null() {
+            drop(Box::from_raw(game));
+        }
+    });
+}
+
+/// Start a new game with the same options
+///
+/// # Safety
+///
+/// `game` must come from `x800_new`
+#[no_mangle]
+pub unsafe extern "C" fn x800_reset(game: *mut Env, seed: u64) {
+    guard((), || drop((*game).reset(seed)));
+}
+
+/// Move in a direction, numbered as in `x800_direction`
+///
+/// # Safety
+///
+/// `game` must come from `x800_new`
+#[no_mangle]
+pub unsafe extern "C" fn x800_move(game: *mut Env, direction: u32) -> c_int {
+    guard(ERROR, || {
+        let env = &mut *game;
+        let action = usize::try_from(direction)
+            .ok()
+            .and_then(|direction| Direction::ALL.get(direction))
+            .and_then(|direction| env.actions().iter().position(|action| action == direction));
+        let (changed, done) = match action {
+            Some(action) => {
+                let (_, _, done, info) = env.step(action);
+                (info.changed, done)
+            }
+            None => (false, env.game.over),
+        };
+        match (changed, done) {
+            (_, true) => OVER,
+            (true, false) => CHANGED,
+            (false, false) => UNCHANGED,
+        }
+    })
+}
+
+/// Whether a move would change the board
+///
+/// # Safety
+///
+/// `game` must come from `x800_new`
+#[no_mangle]
+pub unsafe extern "C" fn x800_legal(game: *const Env, direction: u32) -> bool {
+    guard(false, || {
+        let game = &(*game).game;
+        let direction = usize::try_from(direction)
+            .ok()
+            .and_then(|direction| Direction::ALL.get(direction));
+        match direction {
+            Some(&direction) if game.board.topology().directions().contains(&direction) => {
+                !game.over && game.board.can_move(direction, game.generation + 1)
+            }
+            _ => false,
+        }
+    })
+}
+
+/// Number of columns of the board
+///
+/// # Safety
+///
+/// `game` must come from `x800_new`
+#[no_mangle]
+pub unsafe extern "C" fn x800_width(game: *const Env) -> usize {
+    guard(0, || (*game).game.board.grid().next().map_or(0, <[_]>::len))
+}
+
+/// Number of rows of the board
+///
+/// # Safety
+///
+/// `game` must come from `x800_new`
+#[no_mangle]
+pub unsafe extern "C" fn x800_height(game: *const Env) -> usize {
+    guard(0, || (*game).game.board.grid().count())
+}
+
+/// Value of the tile in a cell, 0 if it's empty, or `X800_SPECIAL` or
+/// `X800_BLOCKED`. Cells off the board are blocked
+///
+/// # Safety
+///
+/// `game` must come from `x800_new`
+#[no_mangle]
+pub unsafe extern "C" fn x800_cell(game: *const Env, row: usize, column: usize) -> u32 {
+    guard(Observation::BLOCKED, || {
+        let board = &(*game).game.board;
+        let tile = board.grid().nth(row).and_then(|row| row.get(column));
+        match tile.copied().map(Observation::level) {
+            Some(level @ (Observation::EMPTY | Observation::SPECIAL | Observation::BLOCKED)) => {
+                level
+            }
+            Some(level) => board.rule().value(level),
+            None => Observation::BLOCKED,
+        }
+    })
+}
+
+/// # Safety
+///
+/// `game` must come from `x800_new`
+#[no_mangle]
+pub unsafe extern "C" fn x800_score(game: *const Env) -> u32 {
+    guard(0, || (*game).game.board.score())
+}
+
+/// Number of moves which changed the board
+///
+/// # Safety
+///
+/// `game` must come from `x800_new`
+#[no_mangle]
+pub unsafe extern "C" fn x800_moves(game: *const Env) -> u32 {
+    guard(0, || (*game).game.moves)
+}
+
+/// Whether the target has been reached
+///
+/// # Safety
+///
+/// `game` must come from `x800_new`
+#[no_mangle]
+pub unsafe extern "C" fn x800_won(game: *const Env) -> bool {
+    guard(false, || (*game).game.has_won())
+}
+
+/// # Safety
+///
+/// `game` must come from `x800_new`
+#[no_mangle]
+pub unsafe extern "C" fn x800_over(game: *const Env) -> bool {
+    guard(true, || (*game).game.over)
+}
+
+/// Write the game's state as JSON, as in `--machine`, like `snprintf`: at
+/// most `size` bytes including a NUL are written, and the length of the
+/// whole state is returned
+///
+/// # Safety
+///
+/// `game` must come from `x800_new`, and `buffer` must be valid for `size`
+/// bytes, or null if `size` is 0
+#[no_mangle]
+pub unsafe extern "C" fn x800_serialise(
+    game: *const Env,
+    buffer: *mut c_char,
+    size: usize,
+) -> usize {
+    guard(0, || {
+        let mut state = String::new();
+        if machine::state(&(*game).game, &mut state).is_err() {
+            return 0;
+        }
+        if size != 0 {
+            let buffer = slice::from_raw_parts_mut(buffer.cast::<u8>(), size);
+            let length = state.len().min(size - 1);
+            buffer[..length].copy_from_slice(&state.as_bytes()[..length]);
+            buffer[length] = 0;
+        }
+        state.len()
+    })
+}
+
+#[test]
+fn ffi_test() {
+    unsafe {
+        assert!(x800_new(c"--topology=sphere".as_ptr(), 1).is_null());
+        let game = x800_new(c"--merge=fibonacci".as_ptr(), 1);
+        assert_eq!((x800_width(game), x800_height(game)), (4, 4));
+        assert_eq!(x800_cell(game, 4, 0), Observation::BLOCKED);
+
+        // Directions off a square board do nothing
+        assert!(!x800_legal(game, 9));
+        assert_eq!(x800_move(game, 9), UNCHANGED);
+        let direction = (0..4)
+            .find(|&direction| x800_legal(game, direction))
+            .unwrap();
+        assert_eq!(x800_move(game, direction), CHANGED);
+        assert_eq!(x800_moves(game), 1);
+
+        let length = x800_serialise(game, ptr::null_mut(), 0);
+        let mut buffer = vec![0 as c_char; length + 1];
+        assert_eq!(
+            x800_serialise(game, buffer.as_mut_ptr(), buffer.len()),
+            length
+        );
+        let state = CStr::from_ptr(buffer.as_ptr()).to_str().unwrap();
+        assert!(state.starts_with("{\"board\":[[") && state.len() == length);
+        x800_free(game);
+        assert_eq!(x800_abi_version(), ABI_VERSION);
+    }
+}
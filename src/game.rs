@@ -0,0 +1,353 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::time;
+
+use crate::board::{Action, Board, Direction, Generation, Power, Rocks, SpawnRules, Topology};
+use crate::challenge::Challenge;
+use crate::config::Config;
+use crate::date::Date;
+use crate::puzzle::Puzzle;
+
+// Player moves searched ahead by the evil spawner
+const EVIL_DEPTH: u32 = 2;
+
+/// Highest scores seen so far
+#[derive(Clone, Copy, Default)]
+pub struct Best {
+    /// Highest score in this process
+    pub session: u32,
+    /// Highest score ever recorded on this machine
+    pub all_time: u32,
+    /// Fastest race won in this process, in seconds
+    pub session_seconds: Option<u64>,
+    /// Fastest race ever won on this machine, in seconds
+    pub all_time_seconds: Option<u64>,
+}
+
+impl Best {
+    #[inline]
+    pub fn update(&mut self, score: u32) {
+        self.session = self.session.max(score);
+        self.all_time = self.all_time.max(score);
+    }
+
+    /// Keep the time of a race won
+    pub fn update_seconds(&mut self, seconds: u64) {
+        let fastest = |best: Option<u64>| Some(best.map_or(seconds, |best| best.min(seconds)));
+        self.session_seconds = fastest(self.session_seconds);
+        self.all_time_seconds = fastest(self.all_time_seconds);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Playing,
+    /// Reached the target, waiting for the player to keep going or stop
+    Won,
+    KeepGoing,
+}
+
+/// Result of applying one action to a game
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    Unchanged,
+    Changed,
+    Over,
+    NewGame,
+    Replay,
+    Quit,
+}
+
+/// One game of x800, along with its bookkeeping
+#[derive(Clone)]
+pub struct Game {
+    pub board: Board,
+    pub phase: Phase,
+    /// Set once the game has ended, while the player chooses what's next
+    pub over: bool,
+    /// Power of the tile which wins the game
+    pub target: Power,
+    pub spawn: SpawnRules,
+    /// Place new tiles adversarially rather than at random
+    pub evil: bool,
+    pub rocks: Rocks,
+    pub challenge: Challenge,
+    /// Date of the daily game, which only counts with that day's seed
+    pub daily: Option<Date>,
+    pub puzzle: Option<Puzzle>,
+    /// Seat in a split-screen race, counted from zero
+    pub player: Option<usize>,
+    /// Raced over the network, where there are no rematches
+    pub online: bool,
+    /// Number of tiles spawned from the puzzle's sequence
+    pub spawns: usize,
+    pub rng: fastrand::Rng,
+    pub seed: u64,
+    /// Counter used to prevent a tile merging twice in one move
+    pub generation: Generation,
+    /// Number of moves which changed the board
+    pub moves: u32,
+    pub started: time::Instant,
+    pub best: Best,
+}
+
+impl Game {
+    pub fn new(seed: u64, config: &Config, best: Best) -> Self {
+        let mut game = Game {
+            board: Board::new(config.merge, config.topology),
+            phase: Phase::Playing,
+            over: false,
+            target: config.target,
+            spawn: config.spawn.clone(),
+            evil: config.evil,
+            rocks: config.rocks,
+            challenge: config.challenge,
+            daily: config.daily,
+            puzzle: config.puzzle.clone(),
+            player: None,
+            online: false,
+            spawns: 0,
+            rng: fastrand::Rng::with_seed(seed),
+            seed,
+            generation: 0,
+            moves: 0,
+            started: time::Instant::now(),
+            best,
+        };
+        // Puzzles start from their own board, without chance
+        if let Some(puzzle) = &game.puzzle {
+            game.board = Board::from_rows(puzzle.board);
+            game.target = puzzle.target();
+            game.challenge = Challenge::Moves(puzzle.moves);
+            return game;
+        }
+
+        // Starting tiles and rocks are always random
+        for _ in 0..game.rocks.initial {
+            game.board.spawn_rock(&mut game.rng);
+        }
+        for _ in 0..game.spawn.initial {
+            if !game
+                .board
+                .spawn_tile(game.generation, &game.spawn, &mut game.rng)
+            {
+                break;
+            }
+        }
+        game
+    }
+
+    /// Read a key as an action. On hex boards and cubes, 'q' moves
+    /// except when answering a prompt
+    #[inline]
+    pub fn parse(&self, input: u8) -> Action {
+        match self.board.topology() {
+            _ if self.over || self.phase == Phase::Won => Action::parse(input),
+            Topology::Hex(_) => Action::parse_hex(input),
+            Topology::Cube => Action::parse_cube(input),
+            Topology::Flat | Topology::Torus => Action::parse(input),
+        }
+    }
+
+    /// Apply one player action
+    #[inline]
+    pub fn act(&mut self, action: Action) -> Step {
+        match action {
+            Action::NewGame => Step::NewGame,
+            Action::Replay => Step::Replay,
+            Action::Shutdown => Step::Quit,
+            Action::Stop if self.over => Step::Quit,
+            _ if self.over => Step::Unchanged,
+            _ if self.time_left() == Some(0) => Step::Over,
+            Action::Direction(direction) => {
+                // Moves wait for an answer to the win prompt, and moves the
+                // board lacks do nothing, even when it's full
+                if self.phase == Phase::Won || self.board.topology().check(direction).is_err() {
+                    return Step::Unchanged;
+                }
+
+                self.generation += 1;
+                let moved = self.board.update(direction, self.generation);
+
+                // If the move had no effect on a non-full board,
+                // skip adding a new tile
+                if !moved && self.board.has_space() {
+                    return Step::Unchanged;
+                }
+                if moved {
+                    self.moves += 1;
+                    self.best.update(self.board.score());
+                    self.check_win();
+                    // Races and puzzles end at the target
+                    if self.has_won() && self.challenge == Challenge::Race {
+                        let seconds = self.started.elapsed().as_secs();
+                        self.best.update_seconds(seconds);
+                        return Step::Over;
+                    }
+                    if self.has_won() && self.puzzle.is_some() {
+                        return Step::Over;
+                    }
+                }
+
+                // Add new tiles if possible
+                // Has the player already used their last move?
+                let mut spawned = false;
+                for _ in 0..self.spawn.per_turn {
+                    if !self.spawn_tile() {
+                        break;
+                    }
+                    spawned = true;
+                }
+                if moved && self.rocks.is_due(self.moves) {
+                    self.board.spawn_rock(&mut self.rng);
+                }
+                if spawned && self.challenge.moves_left(self.moves) != Some(0) {
+                    Step::Changed
+                } else {
+                    Step::Over
+                }
+            }
+            Action::Keep | Action::Stop if self.phase == Phase::Won => {
+                if action == Action::Stop {
+                    return Step::Over;
+                }
+                self.phase = Phase::KeepGoing;
+                Step::Changed
+            }
+            Action::Keep | Action::Stop | Action::Continue => Step::Unchanged,
+        }
+    }
+
+    #[inline]
+    pub fn spawn_tile(&mut self) -> bool {
+        if let Some(puzzle) = &self.puzzle {
+            let (power, cell) = puzzle.spawns[self.spawns % puzzle.spawns.len()];
+            self.spawns += 1;
+            self.board.spawn_fixed(self.generation, power, cell)
+        } else if self.evil {
+            self.board
+                .spawn_evil(self.generation, &self.spawn, EVIL_DEPTH)
+        } else {
+            self.board
+                .spawn_tile(self.generation, &self.spawn, &mut self.rng)
+        }
+    }
+
+    /// Moves which would change the board
+    pub fn legal_moves(&self) -> impl Iterator<Item = Direction> + '_ {
+        let directions = self.board.topology().directions();
+        directions
+            .iter()
+            .copied()
+            .filter(|&direction| self.board.can_move(direction, self.generation + 1))
+    }
+
+    /// Pause for the win prompt the first time the target is reached
+    #[inline]
+    pub fn check_win(&mut self) {
+        if self.phase == Phase::Playing && self.board.max_tile() >= self.target {
+            self.phase = Phase::Won;
+        }
+    }
+
+    /// Seconds remaining in a timed game
+    #[inline]
+    pub fn time_left(&self) -> Option<u64> {
+        self.challenge.time_left(self.started.elapsed().as_secs())
+    }
+
+    pub fn has_won(&self) -> bool {
+        self.phase != Phase::Playing
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.puzzle.is_some() && self.has_won()
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn game_from(rows: crate::board::Rows, args: &[&str]) -> Game {
+    let config = Config::from_args(args.iter().map(ToString::to_string)).unwrap();
+    let mut game = Game::new(1, &config, Best::default());
+    game.board = Board::from_rows(rows);
+    game
+}
+
+#[test]
+fn game_win_prompt_test() {
+    let left = Action::Direction(Direction::Left);
+    let mut rows = [[Some(0); 4]; 4];
+    rows[0] = [Some(10), Some(10), Some(0), Some(0)];
+
+    // Moves wait for an answer once the target is reached
+    let mut game = game_from(rows, &[]);
+    assert_eq!(game.act(left), Step::Changed);
+    assert_eq!(game.phase, Phase::Won);
+    assert_eq!(game.act(left), Step::Unchanged);
+    assert_eq!(game.act(Action::Continue), Step::Unchanged);
+    assert_eq!(game.act(Action::Keep), Step::Changed);
+    assert_eq!(game.phase, Phase::KeepGoing);
+    assert_eq!(game.act(Action::Keep), Step::Unchanged);
+
+    let mut game = game_from(rows, &[]);
+    game.act(left);
+    assert_eq!(game.act(Action::Stop), Step::Over);
+}
+
+#[test]
+fn game_end_screen_test() {
+    let mut game = game_from([[Some(0); 4]; 4], &[]);
+    game.over = true;
+    assert_eq!(game.act(Action::NewGame), Step::NewGame);
+    assert_eq!(game.act(Action::Replay), Step::Replay);
+    assert_eq!(game.act(Action::Stop), Step::Quit);
+    assert_eq!(game.act(Action::Shutdown), Step::Quit);
+    assert_eq!(game.act(Action::Keep), Step::Unchanged);
+    assert_eq!(game.act(Action::Direction(Direction::Up)), Step::Unchanged);
+}
+
+#[test]
+fn game_challenge_endings_test() {
+    let left = Action::Direction(Direction::Left);
+    let mut rows = [[Some(0); 4]; 4];
+    rows[0] = [Some(8), Some(8), Some(0), Some(0)];
+
+    // Races end at their target of 512, and keep the time taken
+    let mut game = game_from(rows, &["--challenge=race"]);
+    assert_eq!(game.act(left), Step::Over);
+    assert_eq!(game.best.session_seconds, Some(0));
+    let mut game = game_from(rows, &[]);
+    assert_eq!(game.act(left), Step::Changed);
+
+    // Move limits end after the last move, and timed games at the time
+    let mut game = game_from(rows, &["--challenge=moves:2"]);
+    assert_eq!(game.act(left), Step::Changed);
+    assert_eq!(game.act(Action::Direction(Direction::Right)), Step::Over);
+    let mut game = game_from(rows, &["--challenge=timed:60"]);
+    assert_eq!(game.act(left), Step::Changed);
+    game.started = time::Instant::now()
+        .checked_sub(time::Duration::from_mins(1))
+        .unwrap();
+    assert_eq!(game.time_left(), Some(0));
+    assert_eq!(game.act(Action::Direction(Direction::Right)), Step::Over);
+}
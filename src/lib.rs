@@ -20,11 +20,83 @@
   SOFTWARE.
 */
 mod board;
+mod challenge;
 mod colour;
+mod config;
+mod daily;
+mod date;
+mod env;
+mod ffi;
+mod game;
+mod machine;
+mod net;
 mod play;
-use play::{play, Input};
+mod puzzle;
+// pyo3's generated wrappers convert errors into themselves
+#[cfg(feature = "python")]
+#[allow(clippy::useless_conversion)]
+mod python;
+mod scores;
+mod spectate;
+mod stats;
+mod status;
+mod store;
+mod telnet;
+mod terminal;
+mod versus;
 
+use std::io::{self, Write};
+
+use config::Command;
+
+pub use board::constants::GAME_FAILURE_MESSAGE;
+pub use board::Direction;
+pub use config::{Config, USAGE};
+pub use env::{Env, Info, Observation, Shaping};
+pub use play::play;
+pub use terminal::{FrameSink, Frames, InputSource, Memory, Pty, Tty};
+
+/// Run the command chosen by `config`
+///
+/// # Errors
+///
+/// Returns an error if the game fails, or if stored data can't be read
+pub fn run(config: &Config) -> io::Result<()> {
+    match config.command {
+        Command::Play => play(&mut Tty::open(), Box::new(io::stdout()), config),
+        Command::Daily => daily::run(config),
+        Command::Versus => versus::run(config),
+        Command::Host => net::host(config),
+        Command::Join => net::join(config),
+        Command::Watch => spectate::watch(config),
+        Command::Serve => telnet::serve(config),
+        Command::Machine => machine::run(config),
+        Command::Puzzles => puzzle::list(&mut io::stdout().lock()),
+        Command::Scores => scores::Table::load()?.list(&mut io::stdout().lock()),
+        Command::Stats => {
+            let mut screen = String::new();
+            stats::Stats::load()?
+                .draw(&mut screen)
+                .map_err(|_| io::Error::other(GAME_FAILURE_MESSAGE))?;
+            io::stdout().lock().write_all(screen.as_bytes())
+        }
+    }
+}
+
+/// Play one game from `input`, with tiles drawn from `seed`
+///
+/// # Panics
+///
+/// Panics if the game fails
 pub fn fuzz(input: &[u8], seed: u64) {
-    fastrand::seed(seed);
-    play(&Input::Slice(input)).unwrap();
+    let config = Config {
+        seed: Some(seed),
+        ..Config::default()
+    };
+    play(
+        &mut Memory::new(input),
+        Box::new(Frames::default()),
+        &config,
+    )
+    .unwrap();
 }
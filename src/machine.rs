@@ -0,0 +1,425 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::{
+    collections::VecDeque,
+    fmt::{self, Write as FmtWrite},
+    io::{self, BufRead, Write},
+};
+
+use crate::board::{
+    constants::GAME_FAILURE_MESSAGE, merge::Classic, Action, Direction, MergeRule, Tile,
+};
+use crate::config::Config;
+use crate::game::{Best, Game, Phase, Step};
+
+// Moves which can be undone. Older ones are forgotten
+const MAX_UNDO: usize = 1000;
+// Arrays and objects nested deeper than this are refused
+const MAX_DEPTH: usize = 32;
+
+/// A JSON value, as far as commands need to know it
+enum Value {
+    String(String),
+    Bool(bool),
+    Other,
+}
+
+/// Reads JSON values from the front of a line
+struct Scanner<'a>(&'a str);
+
+impl Scanner<'_> {
+    fn skip_whitespace(&mut self) {
+        self.0 = self.0.trim_start_matches([' ', '\t', '\n', '\r']);
+    }
+
+    /// Whether `c` comes next, skipping it if so
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        self.0.strip_prefix(c).map(|rest| self.0 = rest).is_some()
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(format!("expected '{c}'"))
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+        let mut chars = self.0.char_indices();
+        loop {
+            let (i, c) = chars.next().ok_or("unterminated string")?;
+            match c {
+                '"' => {
+                    self.0 = &self.0[i + 1..];
+                    return Ok(string);
+                }
+                '\\' => {
+                    let (_, escape) = chars.next().ok_or("unterminated string")?;
+                    string.push(match escape {
+                        '"' | '\\' | '/' => escape,
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => {
+                            let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                            let code = (hex.len() == 4)
+                                .then(|| u32::from_str_radix(&hex, 16).ok())
+                                .flatten()
+                                .ok_or("malformed escape")?;
+                            // Surrogate pairs never spell a command
+                            char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        _ => return Err("malformed escape".to_string()),
+                    });
+                }
+                _ if c.is_control() => return Err("control character in string".to_string()),
+                _ => string.push(c),
+            }
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, String> {
+        if depth > MAX_DEPTH {
+            return Err("nested too deeply".to_string());
+        }
+        self.skip_whitespace();
+        if self.0.starts_with('"') {
+            return self.string().map(Value::String);
+        }
+        for (open, close) in [('{', '}'), ('[', ']')] {
+            if !self.eat(open) {
+                continue;
+            }
+            if !self.eat(close) {
+                loop {
+                    if open == '{' {
+                        self.string()?;
+                        self.expect(':')?;
+                    }
+                    self.value(depth + 1)?;
+                    if self.eat(close) {
+                        break;
+                    }
+                    self.expect(',')?;
+                }
+            }
+            return Ok(Value::Other);
+        }
+
+        let end = self
+            .0
+            .find(|c: char| !(c.is_ascii_alphanumeric() || "+-.".contains(c)))
+            .unwrap_or(self.0.len());
+        let (word, rest) = self.0.split_at(end);
+        let value = match word {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            "null" => Value::Other,
+            _ if word.starts_with(|c: char| c == '-' || c.is_ascii_digit())
+                && word.parse::<f64>().is_ok() =>
+            {
+                Value::Other
+            }
+            _ => return Err(format!("unexpected {}", Quoted(word))),
+        };
+        self.0 = rest;
+        Ok(value)
+    }
+}
+
+/// One command from a program playing the game
+///
+/// Commands are JSON objects, one per line, holding one command member:
+/// `{"move":"left"}` or `{"undo":true}`. Other members are ignored. Moves
+/// are named as in `Direction::name`.
+#[derive(Clone, Copy)]
+enum Request {
+    Move(Direction),
+    Undo,
+}
+
+impl Request {
+    fn parse(line: &str) -> Result<Self, String> {
+        let mut scanner = Scanner(line);
+        let mut request = None;
+        scanner.expect('{')?;
+        if !scanner.eat('}') {
+            loop {
+                let name = scanner.string()?;
+                scanner.expect(':')?;
+                let command = match (name.as_str(), scanner.value(1)?) {
+                    ("move", Value::String(value)) => Some(
+                        Direction::parse(&value)
+                            .map(Request::Move)
+                            .ok_or(format!("unknown move {}", Quoted(&value)))?,
+                    ),
+                    ("undo", Value::Bool(true)) => Some(Request::Undo),
+                    ("move" | "undo", _) => return Err(format!("malformed {name}")),
+                    _ => None,
+                };
+                if let Some(command) = command {
+                    if request.replace(command).is_some() {
+                        return Err("expected one command".to_string());
+                    }
+                }
+                if scanner.eat('}') {
+                    break;
+                }
+                scanner.expect(',')?;
+            }
+        }
+        scanner.skip_whitespace();
+        if !scanner.0.is_empty() {
+            return Err("expected one object".to_string());
+        }
+        request.ok_or("expected a command".to_string())
+    }
+}
+
+/// A string written as JSON
+struct Quoted<'a>(&'a str);
+
+impl fmt::Display for Quoted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            match c {
+                '"' | '\\' => write!(f, "\\{c}")?,
+                _ if c.is_control() => write!(f, "\\u{:04x}", u32::from(c))?,
+                _ => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+/// A tile written as JSON: its value, zero when empty, null outside the
+/// board's shape, or the name of a special tile
+struct Cell<'a>(&'a Game, Tile);
+
+impl fmt::Display for Cell<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.1 {
+            Tile::Number(power, _) => write!(f, "{}", self.0.board.rule().value(power)),
+            Tile::Empty() => write!(f, "0"),
+            Tile::Rock() => write!(f, "\"rock\""),
+            Tile::Joker() => write!(f, "\"joker\""),
+            Tile::Bomb() => write!(f, "\"bomb\""),
+            Tile::Double(power) => write!(f, "\"x{}\"", Classic.value(power)),
+            Tile::Edge(_) | Tile::Corner(_) | Tile::Void() => write!(f, "null"),
+        }
+    }
+}
+
+/// The whole state of the game, as one line of JSON
+pub(crate) fn state(game: &Game, line: &mut String) -> fmt::Result {
+    line.push_str("{\"board\":[");
+    for (r, row) in game.board.grid().enumerate() {
+        line.push_str(if r == 0 { "[" } else { ",[" });
+        for (c, &tile) in row.iter().enumerate() {
+            if c != 0 {
+                line.push(',');
+            }
+            write!(line, "{}", Cell(game, tile))?;
+        }
+        line.push(']');
+    }
+    write!(
+        line,
+        "],\"score\":{},\"moves\":{},\"legal\":[",
+        game.board.score(),
+        game.moves
+    )?;
+    if !game.over {
+        for (i, direction) in game.legal_moves().enumerate() {
+            if i != 0 {
+                line.push(',');
+            }
+            write!(line, "{}", Quoted(direction.name()))?;
+        }
+    }
+    line.push_str("],\"spawned\":[");
+    let grid: Vec<&[Tile]> = game.board.grid().collect();
+    for (i, (r, c)) in game.board.spawned().enumerate() {
+        if i != 0 {
+            line.push(',');
+        }
+        write!(
+            line,
+            "{{\"row\":{r},\"column\":{c},\"tile\":{}}}",
+            Cell(game, grid[r][c])
+        )?;
+    }
+    write!(
+        line,
+        "],\"won\":{},\"over\":{}}}",
+        game.has_won(),
+        game.over
+    )
+}
+
+/// Apply one command, keeping earlier states for undoing moves
+fn apply(game: &mut Game, history: &mut VecDeque<Game>, request: Request) -> Result<(), String> {
+    match request {
+        Request::Move(_) if game.over => Err("the game is over".to_string()),
+        Request::Move(direction) => {
+            game.board.topology().check(direction)?;
+            if !game.legal_moves().any(|legal| legal == direction) {
+                return Err(format!("{} changes nothing", direction.name()));
+            }
+            let before = game.clone();
+            if game.act(Action::Direction(direction)) == Step::Over
+                || game.legal_moves().next().is_none()
+            {
+                game.over = true;
+            }
+            // Programs keep going past the target, without a prompt
+            if game.phase == Phase::Won {
+                game.phase = Phase::KeepGoing;
+            }
+            if history.len() == MAX_UNDO {
+                history.pop_front();
+            }
+            history.push_back(before);
+            Ok(())
+        }
+        Request::Undo => {
+            *game = history.pop_back().ok_or("nothing to undo")?;
+            Ok(())
+        }
+    }
+}
+
+/// Play one game by JSON lines on standard input and output
+///
+/// The state is written at the start, and after each command. A command
+/// which can't be carried out gets `{"error":"..."}` instead, and changes
+/// nothing. Games played this way are not recorded.
+///
+/// # Errors
+///
+/// Returns an error if standard input or output fails
+pub fn run(config: &Config) -> io::Result<()> {
+    let seed = config.seed.unwrap_or_else(|| fastrand::u64(..));
+    let mut game = Game::new(seed, config, Best::default());
+    let mut history = VecDeque::new();
+    let mut stdout = io::stdout().lock();
+    let mut line = String::new();
+
+    state(&game, &mut line).map_err(|_| io::Error::other(GAME_FAILURE_MESSAGE))?;
+    writeln!(stdout, "{line}")?;
+    for request in io::stdin().lock().lines() {
+        let request = request?;
+        if request.trim().is_empty() {
+            continue;
+        }
+        line.clear();
+        match Request::parse(&request).and_then(|request| apply(&mut game, &mut history, request))
+        {
+            Ok(()) => state(&game, &mut line),
+            Err(error) => write!(line, "{{\"error\":{}}}", Quoted(&error)),
+        }
+        .map_err(|_| io::Error::other(GAME_FAILURE_MESSAGE))?;
+        writeln!(stdout, "{line}")?;
+    }
+    Ok(())
+}
+
+#[test]
+fn machine_test() {
+    use crate::board::Rows;
+
+    let args = ["--seed=1", "--machine"].map(String::from);
+    let config = Config::from_args(args.into_iter()).unwrap();
+    let mut game = Game::new(1, &config, Best::default());
+    let mut history = VecDeque::new();
+    let start: Rows = game.board.rows();
+
+    // A legal move spawns a tile, and undoing it restores the board
+    let direction = game.legal_moves().next().unwrap();
+    let name = direction.name();
+    let request = Request::parse(&format!(
+        " {{ \"id\" : [1, {{}}], \"mo\\u0076e\" : \"{name}\" }} "
+    ))
+    .unwrap();
+    apply(&mut game, &mut history, request).unwrap();
+    assert_eq!(game.board.spawned().count(), 1);
+    let mut line = String::new();
+    state(&game, &mut line).unwrap();
+    assert!(line.starts_with("{\"board\":[["));
+    assert!(line.ends_with(",\"won\":false,\"over\":false}"));
+
+    apply(
+        &mut game,
+        &mut history,
+        Request::parse("{\"undo\":true}").unwrap(),
+    )
+    .unwrap();
+    assert_eq!(game.board.rows(), start);
+    assert!(apply(&mut game, &mut history, Request::Undo).is_err());
+    for malformed in [
+        "move left",
+        "{\"move\":\"sideways\"}",
+        "{\"undo\":false}",
+        "{\"undo\":true,\"move\":\"left\"}",
+        "{\"undo\":true} {}",
+        "{\"id\":1}",
+        "{\"move\":\"left\"",
+    ] {
+        assert!(Request::parse(malformed).is_err(), "{malformed}");
+    }
+
+    // Moves which change nothing are refused, and a board with no moves
+    // left is over. Only spawns are reported as spawned
+    let (a, b) = (Some(5), Some(6));
+    let rows = [
+        [a, b, a, b],
+        [b, a, b, a],
+        [a, b, a, b],
+        [Some(0), Some(7), Some(8), Some(7)],
+    ];
+    let mut game = crate::game::game_from(rows, &["--machine"]);
+    assert_eq!(game.board.spawned().count(), 0);
+    assert!(apply(&mut game, &mut history, Request::Move(Direction::Right)).is_err());
+    apply(&mut game, &mut history, Request::Move(Direction::Left)).unwrap();
+    assert_eq!(game.board.spawned().collect::<Vec<_>>(), [(3, 3)]);
+    assert!(game.over);
+
+    // Moves a board's shape lacks say so
+    let args = ["--machine", "--topology=hex"].map(String::from);
+    let mut game = Game::new(
+        1,
+        &Config::from_args(args.into_iter()).unwrap(),
+        Best::default(),
+    );
+    assert_eq!(
+        apply(&mut game, &mut history, Request::Move(Direction::Left)),
+        Err("left isn't a move on hex boards".to_string())
+    );
+}
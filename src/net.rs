@@ -0,0 +1,551 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::{
+    fmt::{self, Write as FmtWrite},
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{atomic, Arc, Mutex},
+    thread,
+};
+
+use crate::board::{
+    self,
+    constants::{
+        GAME_FAILURE_MESSAGE, LEFT_SPACE, OPPONENT_LEFT, PLAYERS_DRAW, RACE_LOST, RACE_WON,
+        WAITING_FOR_OPPONENT,
+    },
+    Action, Direction, Output, Screen, Topology,
+};
+use crate::challenge::Challenge;
+use crate::config::Config;
+use crate::game::{Best, Game, Step};
+use crate::spectate::Publisher;
+use crate::status::Layout;
+use crate::terminal::{InputSource, Tty};
+use crate::versus;
+
+pub const DEFAULT_PORT: u16 = 2048;
+
+// First word of the rules sent by the host
+const RULES_PREFIX: &str = "x800";
+// Options a host may set for the joining player, besides the race itself
+const RULES_OPTIONS: [&str; 7] = [
+    "--seed",
+    "--target",
+    "--merge",
+    "--spawn",
+    "--rocks",
+    "--topology",
+    "--evil",
+];
+const RULES_CHALLENGE: &str = "--challenge=race";
+// Space to the left of the opponent's board
+const MINI_SPACE: &str = "   ";
+
+// Longest line either end sends. A longer one ends the race
+const MAX_LINE: u64 = 1024;
+
+/// Lines from the opponent, at most `MAX_LINE` bytes each
+struct Lines(BufReader<TcpStream>);
+
+impl Lines {
+    fn new(stream: &TcpStream) -> io::Result<Self> {
+        Ok(Lines(BufReader::new(stream.try_clone()?)))
+    }
+}
+
+impl Iterator for Lines {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        match (&mut self.0).take(MAX_LINE).read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) if line.ends_with('\n') => {
+                line.pop();
+                if line.ends_with('\r') {
+                    line.pop();
+                }
+                Some(Ok(line))
+            }
+            Ok(read) if read as u64 == MAX_LINE => Some(Err(io::Error::other("line too long"))),
+            result => Some(result.map(|_| line)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Side {
+    Host,
+    Join,
+}
+
+impl Side {
+    fn name(self) -> &'static str {
+        match self {
+            Side::Host => "host",
+            Side::Join => "join",
+        }
+    }
+
+    fn other(self) -> Self {
+        match self {
+            Side::Host => Side::Join,
+            Side::Join => Side::Host,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Outcome {
+    Won(Side),
+    Draw,
+    /// The opponent left, or broke the protocol
+    Left,
+}
+
+/// One line of the race protocol
+///
+/// The host first sends the rules as x800 arguments, including the seed, so
+/// both ends build the same games. Then each end sends its moves, as
+/// `move up`, `move down`, `move left` or `move right`, and `quit` when
+/// leaving. Both ends replay each other's moves, but only the host's replay
+/// counts: it sends `result host`, `result join` or `result draw` once the
+/// race is over, so a joining player can't claim a score they didn't play.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Message {
+    Move(Direction),
+    Result(Outcome),
+    Quit,
+}
+
+impl Message {
+    fn parse(line: &str) -> Option<Self> {
+        let (verb, argument) = line.split_once(' ').unwrap_or((line, ""));
+        match (verb, argument) {
+            // Races are on square boards
+            ("move", name) => Direction::parse(name)
+                .filter(|direction| Topology::Flat.directions().contains(direction))
+                .map(Message::Move),
+            ("result", "host") => Some(Message::Result(Outcome::Won(Side::Host))),
+            ("result", "join") => Some(Message::Result(Outcome::Won(Side::Join))),
+            ("result", "draw") => Some(Message::Result(Outcome::Draw)),
+            ("quit", "") => Some(Message::Quit),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Message::Move(direction) => write!(f, "move {}", direction.name()),
+            Message::Result(Outcome::Won(side)) => write!(f, "result {}", side.name()),
+            Message::Result(Outcome::Draw) => write!(f, "result draw"),
+            Message::Result(Outcome::Left) => Err(fmt::Error),
+            Message::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+/// Arguments which give the joining player the same games as the host
+fn rules(config: &Config, seed: u64) -> String {
+    let rule = config.merge;
+    let mut rules = format!(
+        "{RULES_PREFIX} --seed={seed:x} --target={} --merge={} --spawn={} --rocks={} \
+         --topology={} --challenge=race",
+        rule.value(config.target),
+        rule.name(),
+        config.spawn,
+        config.rocks,
+        config.topology
+    );
+    if config.evil {
+        rules.push_str(" --evil");
+    }
+    rules
+}
+
+/// The joining player's configuration, from the rules sent by the host
+///
+/// The rules come from the network, so only options which shape a race are
+/// taken. Others, such as a puzzle file to open, are refused
+fn parse_rules(line: &str) -> Result<Config, String> {
+    let unexpected = || format!("unexpected rules '{line}'");
+    let args = line.strip_prefix(RULES_PREFIX).ok_or_else(unexpected)?;
+    for arg in args.split_whitespace() {
+        let name = arg.split_once('=').map_or(arg, |(name, _)| name);
+        if arg != RULES_CHALLENGE && !RULES_OPTIONS.contains(&name) {
+            return Err(unexpected());
+        }
+    }
+    let config = Config::from_args(args.split_whitespace().map(String::from))?;
+    let race = config.challenge == Challenge::Race && config.topology.is_square();
+    if !race || config.seed.is_none() {
+        return Err(unexpected());
+    }
+    Ok(config)
+}
+
+/// Whether an address names its port. The text after the last colon of a
+/// bare IPv6 address is part of the address
+fn has_port(address: &str) -> bool {
+    address.parse::<SocketAddr>().is_ok()
+        || address
+            .rsplit_once(':')
+            .is_some_and(|(host, port)| !host.contains(':') && port.parse::<u16>().is_ok())
+}
+
+/// One end of a race: this player's game, and a replay of the opponent's
+struct Match {
+    side: Side,
+    local: Mutex<Game>,
+    remote: Mutex<Game>,
+    /// Set by the host once both games are over
+    outcome: Mutex<Option<Outcome>>,
+    stream: Mutex<TcpStream>,
+}
+
+impl Match {
+    fn new(side: Side, config: &Config, seed: u64, stream: &TcpStream) -> io::Result<Self> {
+        let game = || Game {
+            online: true,
+            ..Game::new(seed, config, Best::default())
+        };
+        stream.set_nodelay(true)?;
+        Ok(Match {
+            side,
+            local: Mutex::new(game()),
+            remote: Mutex::new(game()),
+            outcome: Mutex::new(None),
+            stream: Mutex::new(stream.try_clone()?),
+        })
+    }
+
+    /// Wait for a player to join, and send them the rules
+    fn accept(listener: &TcpListener, config: &Config) -> io::Result<(Self, Lines)> {
+        let (mut stream, _) = listener.accept()?;
+        let seed = config.seed.unwrap_or_else(|| fastrand::u64(..));
+        writeln!(stream, "{}", rules(config, seed))?;
+        let lines = Lines::new(&stream)?;
+        Ok((Match::new(Side::Host, config, seed, &stream)?, lines))
+    }
+
+    /// Join the race hosted at `address`, playing by its rules
+    fn connect(address: &str, port: u16) -> io::Result<(Self, Lines)> {
+        let stream = if has_port(address) {
+            TcpStream::connect(address)?
+        } else {
+            TcpStream::connect((address, port))?
+        };
+
+        let mut lines = Lines::new(&stream)?;
+        let rules = lines
+            .next()
+            .ok_or_else(|| io::Error::other("the host left"))??;
+        let config = parse_rules(&rules).map_err(io::Error::other)?;
+        let seed = config.seed.ok_or_else(|| io::Error::other("no seed"))?;
+        Ok((Match::new(Side::Join, &config, seed, &stream)?, lines))
+    }
+
+    fn game(&self, side: Side) -> &Mutex<Game> {
+        if side == self.side {
+            &self.local
+        } else {
+            &self.remote
+        }
+    }
+
+    fn send(&self, message: Message) -> io::Result<()> {
+        writeln!(self.stream.lock().unwrap(), "{message}")
+    }
+
+    /// Make a move in this player's game, and tell the opponent
+    fn play(&self, direction: Direction) -> io::Result<()> {
+        let step = {
+            let mut game = self.local.lock().unwrap();
+            if game.over {
+                return Ok(());
+            }
+            game.act(Action::Direction(direction))
+        };
+        self.send(Message::Move(direction))?;
+        if step == Step::Over {
+            self.finish(self.side)?;
+        }
+        Ok(())
+    }
+
+    /// Apply one line from the opponent
+    ///
+    /// # Errors
+    ///
+    /// Returns an error on a malformed or unexpected line
+    fn receive(&self, line: &str) -> io::Result<()> {
+        match Message::parse(line) {
+            Some(Message::Move(direction)) => {
+                let step = {
+                    let mut game = self.remote.lock().unwrap();
+                    // Moves made before hearing the race ended don't count
+                    if game.over {
+                        return Ok(());
+                    }
+                    game.act(Action::Direction(direction))
+                };
+                if step == Step::Over {
+                    self.finish(self.side.other())?;
+                }
+                Ok(())
+            }
+            Some(Message::Result(outcome)) if self.side == Side::Join => {
+                *self.outcome.lock().unwrap() = Some(outcome);
+                self.local.lock().unwrap().over = true;
+                self.remote.lock().unwrap().over = true;
+                Ok(())
+            }
+            Some(Message::Quit) => Err(io::Error::other("the opponent left")),
+            _ => Err(io::Error::other(format!("unexpected line '{line}'"))),
+        }
+    }
+
+    /// End one player's game. The first to the target ends the race for both
+    fn finish(&self, side: Side) -> io::Result<()> {
+        let won = {
+            let mut game = self.game(side).lock().unwrap();
+            game.over = true;
+            game.has_won()
+        };
+        if won {
+            self.game(side.other()).lock().unwrap().over = true;
+        }
+        if self.side == Side::Host {
+            self.settle()?;
+        }
+        Ok(())
+    }
+
+    /// Decide and announce the outcome, once both games are over
+    fn settle(&self) -> io::Result<()> {
+        let outcome = {
+            let local = self.local.lock().unwrap();
+            let remote = self.remote.lock().unwrap();
+            if !(local.over && remote.over) {
+                return Ok(());
+            }
+            // The host is seated first
+            match versus::winner(&[local, remote]) {
+                Some(0) => Outcome::Won(Side::Host),
+                Some(_) => Outcome::Won(Side::Join),
+                None => Outcome::Draw,
+            }
+        };
+        {
+            let mut settled = self.outcome.lock().unwrap();
+            if settled.is_some() {
+                return Ok(());
+            }
+            *settled = Some(outcome);
+        }
+        self.send(Message::Result(outcome))
+    }
+
+    /// The opponent has gone, so the race ends where it stands
+    fn leave(&self) {
+        self.outcome.lock().unwrap().get_or_insert(Outcome::Left);
+        self.local.lock().unwrap().over = true;
+        self.remote.lock().unwrap().over = true;
+        let _ = self.stream.lock().unwrap().shutdown(Shutdown::Both);
+    }
+}
+
+/// This player's board, the opponent's in miniature, then the outcome
+impl Screen for Match {
+    fn draw_frame(&self, layout: &Layout, buffer: &mut String) -> fmt::Result {
+        let local = self.local.lock().unwrap();
+        let remote = self.remote.lock().unwrap();
+        let mut frames = [String::new(), String::new()];
+        board::draw_game(&local, layout, &mut frames[0])?;
+        write!(frames[1], "{MINI_SPACE}opponent\r\n\n")?;
+        remote.board.draw_mini(MINI_SPACE, &mut frames[1])?;
+        write!(frames[1], "{MINI_SPACE}score {}\r\n", remote.board.score())?;
+        board::compose(&frames, buffer);
+
+        let outcome = match *self.outcome.lock().unwrap() {
+            None if local.over => WAITING_FOR_OPPONENT,
+            None => return Ok(()),
+            Some(Outcome::Won(side)) if side == self.side => RACE_WON,
+            Some(Outcome::Won(_)) => RACE_LOST,
+            Some(Outcome::Draw) => PLAYERS_DRAW,
+            Some(Outcome::Left) => OPPONENT_LEFT,
+        };
+        write!(buffer, "\r\n{LEFT_SPACE}{outcome}")
+    }
+}
+
+/// Host a race, waiting for an opponent to join
+///
+/// # Errors
+///
+/// Returns an error if the port can't be listened on, or the game fails
+pub fn host(config: &Config) -> io::Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", config.port))?;
+    println!("waiting for an opponent on port {}", config.port);
+    let (game, lines) = Match::accept(&listener, config)?;
+    run(game, lines, config)
+}
+
+/// Join the race hosted at `config.address`
+///
+/// # Errors
+///
+/// Returns an error if the host can't be reached, or the game fails
+pub fn join(config: &Config) -> io::Result<()> {
+    let address = config.address.as_deref().unwrap_or_default();
+    let (game, lines) = Match::connect(address, config.port)?;
+    run(game, lines, config)
+}
+
+/// Play out a race at the terminal, while a thread applies the opponent's moves
+///
+/// # Panics
+///
+/// Panics if a game's state is poisoned by another thread
+fn run(game: Match, lines: Lines, config: &Config) -> io::Result<()> {
+    let game = Arc::new(game);
+    let publisher = config
+        .publish
+        .as_deref()
+        .map(Publisher::listen)
+        .transpose()?;
+    let mut tty = Tty::open();
+
+    let done = Arc::new(atomic::AtomicBool::new(false));
+    let draw_join = {
+        let game = Arc::clone(&game);
+        let layout = config.layout.clone();
+        let done = Arc::clone(&done);
+        thread::spawn(move || {
+            let output = Output {
+                sink: Box::new(io::stdout()),
+                publisher,
+            };
+            board::draw(&*game, &layout, output, &done).expect(GAME_FAILURE_MESSAGE);
+        })
+    };
+    draw_join.thread().unpark();
+
+    let receive_join = {
+        let game = Arc::clone(&game);
+        let draw = draw_join.thread().clone();
+        thread::spawn(move || {
+            for line in lines {
+                if line.and_then(|line| game.receive(&line)).is_err() {
+                    break;
+                }
+                draw.unpark();
+            }
+            game.leave();
+            draw.unpark();
+        })
+    };
+
+    loop {
+        let Some(key) = tty.read_key(None) else {
+            continue;
+        };
+        match Action::parse(key) {
+            // A lost connection shows as the opponent leaving
+            Action::Direction(direction) => {
+                let _ = game.play(direction);
+            }
+            Action::Stop if game.local.lock().unwrap().over => break,
+            Action::Shutdown => break,
+            _ => continue,
+        }
+        draw_join.thread().unpark();
+    }
+
+    // The last frame is drawn before hanging up
+    done.store(true, atomic::Ordering::Relaxed);
+    draw_join.thread().unpark();
+    draw_join
+        .join()
+        .map_err(|_| io::Error::other(GAME_FAILURE_MESSAGE))?;
+    let _ = game.send(Message::Quit);
+    game.leave();
+    receive_join
+        .join()
+        .map_err(|_| io::Error::other(GAME_FAILURE_MESSAGE))?;
+    Ok(())
+}
+
+#[test]
+fn net_race_test() {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let joining = thread::spawn(move || Match::connect(&address, DEFAULT_PORT).unwrap());
+    let args = ["host", "--seed=7", "--target=64"].map(String::from);
+    let config = Config::from_args(args.into_iter()).unwrap();
+    let (host, mut lines) = Match::accept(&listener, &config).unwrap();
+    let (join, _) = joining.join().unwrap();
+
+    // Both ends start from the same seed, and the host replays each move
+    let board = |game: &Mutex<Game>| game.lock().unwrap().board.rows();
+    assert_eq!(board(&host.local), board(&join.local));
+    for direction in [
+        Direction::Left,
+        Direction::Up,
+        Direction::Right,
+        Direction::Up,
+    ] {
+        join.play(direction).unwrap();
+        host.receive(&lines.next().unwrap().unwrap()).unwrap();
+    }
+    assert_eq!(board(&host.remote), board(&join.local));
+
+    // Only moves are accepted from the joining player
+    for line in ["score 99999", "move sideways", "result join", ""] {
+        assert!(host.receive(line).is_err());
+    }
+
+    // Lines are only read so far
+    let long = "move left".repeat(200);
+    writeln!(join.stream.lock().unwrap(), "{long}").unwrap();
+    assert!(lines.next().unwrap().is_err());
+
+    // Only options which shape a race are taken from the host
+    assert!(parse_rules(&rules(&config, 7)).is_ok());
+    for rules in [
+        "x800 --seed=7 --challenge=race --puzzle=/etc/passwd",
+        "x800 --seed=7 --challenge=race --spawn=2:1/4000000000",
+        "x800 --seed=7 --challenge=race --rocks=4000000000",
+        "x800 --seed=7 --challenge=race --topology=hex",
+        "x800 --seed=7 --challenge=timed",
+        "x800 --challenge=race",
+    ] {
+        assert!(parse_rules(rules).is_err(), "{rules}");
+    }
+
+    assert!(has_port("[::1]:2048") && has_port("localhost:2048"));
+    assert!(!has_port("::1") && !has_port("localhost"));
+}
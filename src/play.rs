@@ -21,90 +21,122 @@
 */
 
 use std::{
-    collections::BTreeSet,
-    io::{self, Read},
-    mem,
+    io,
     sync::{atomic, Arc, Mutex},
     thread,
 };
 
-use crate::board::{
-    self,
-    constants::{self, DEFAULT_BOARD, END_OF_GAME_CHARACTER},
-    Generation,
+use crate::board::{self, constants};
+use crate::{
+    challenge::Challenge,
+    config::Config,
+    daily::Daily,
+    game::{Best, Game, Step},
+    puzzle,
+    scores::{Entry, Table},
+    spectate::Publisher,
+    stats::Stats,
+    status::TICK_DURATION,
+    terminal::{FrameSink, InputSource},
 };
-use board::Action;
+use board::{Action, Output};
 
-const INITIAL_TILES_COUNT: u32 = 2;
 const EXPECT_NOT_FUZZING: &str = "Expected cfg!(not(fuzzing))";
 
-#[inline(always)]
-fn startup(input: &Input) -> Option<Io> {
-    // Early exit
-    if cfg!(fuzzing) {
+/// What's kept of a finished game
+enum Records {
+    Game(Box<(Entry, Option<Daily>)>),
+    /// Name of a solved puzzle, and the moves taken
+    Solved(String, u32),
+}
+
+/// End a game, returning its records when they should be kept. Only a game
+/// played to its `ended` counts as the day's daily result
+fn finish(game: &mut Game, ended: bool) -> Option<Records> {
+    if game.over {
+        return None;
+    }
+    game.over = true;
+    if cfg!(fuzzing) || game.moves == 0 {
         return None;
     }
+    match &game.puzzle {
+        Some(puzzle) => game
+            .is_solved()
+            .then(|| Records::Solved(puzzle.name.clone(), game.moves)),
+        None => Some(Records::Game(Box::new((
+            Entry::from_game(game),
+            Daily::from_game(game).filter(|_| ended),
+        )))),
+    }
+}
 
-    // In case of not fuzzing, we must return Some(_)
-    let fd = libc::STDIN_FILENO;
-    let return_ios: libc::termios;
-    unsafe {
-        let mut ios: libc::termios = mem::zeroed();
-        // Read current configuration
-        libc::tcgetattr(fd, &mut ios);
-        // Copy
-        return_ios = ios;
-
-        // Enable and set raw mode in case of terminal
-        if let Input::Interactive = input {
-            libc::cfmakeraw(&mut ios);
-            libc::tcsetattr(fd, libc::TCSANOW, &ios);
+/// Failing to save should not fail the game
+fn record(records: Option<Records>) {
+    match records {
+        Some(Records::Game(records)) => {
+            let (entry, daily) = *records;
+            Stats::record(&entry).ok();
+            Table::record(entry).ok();
+            if let Some(daily) = daily {
+                daily.record().ok();
+            }
+        }
+        Some(Records::Solved(name, moves)) => {
+            puzzle::record(&name, moves).ok();
         }
+        None => {}
     }
-    Some(Io(fd, return_ios))
 }
 
-#[allow(dead_code)]
-pub enum Input<'a> {
-    Slice(&'a [u8]),
-    Interactive,
-}
+/// Play x800 until the player quits, with keys from `input` and frames
+/// drawn on `sink`
+///
+/// # Errors
+///
+/// Returns an error if the board-drawing thread fails, or if spectators
+/// can't be accepted
+///
+/// # Panics
+///
+/// Panics if the game state is poisoned by another thread
+pub fn play(
+    input: &mut dyn InputSource,
+    sink: Box<dyn FrameSink>,
+    config: &Config,
+) -> io::Result<()> {
+    // Runtime storage
+    let seed = config.seed.unwrap_or_else(|| fastrand::u64(..));
+    let game = Arc::new(Mutex::new(Game::new(seed, config, Best::default())));
+
+    // Load the all-time best, except while fuzzing or for puzzles
+    if cfg!(not(fuzzing)) && config.puzzle.is_none() {
+        let mut unlocked = game.lock().unwrap();
+        let category = Entry::from_game(&unlocked).category();
+        let table = Table::load().unwrap_or_default();
+        unlocked.best.all_time = table.best(&category);
+        unlocked.best.all_time_seconds = table.fastest(&category);
+    }
 
-struct Io(libc::c_int, libc::termios);
+    let publisher = match &config.publish {
+        Some(address) if cfg!(not(fuzzing)) => Some(Publisher::listen(address)?),
+        _ => None,
+    };
+    let output = Output { sink, publisher };
 
-#[inline(always)]
-pub fn play(input: &Input) -> Result<(), ()> {
-    // Runtime storage
-    let board = Arc::new(Mutex::new(DEFAULT_BOARD.clone()));
-
-    // Closure called for program input
-    let stdin_reader = || -> Action {
-        // Read a byte from stdin
-        let mut buffer = [0_u8; 1];
-        if let Err(_) = io::stdin().read_exact(&mut buffer) {
-            return Action::Shutdown;
+    // Games played elsewhere, or by another program, are not recorded here
+    let keep = input.keeps_records();
+    let record = |records| {
+        if keep {
+            record(records);
         }
-        Action::parse(buffer[0])
     };
 
-    // Pre-setup for slice input
-    let mut iter = match input {
-        Input::Slice(slice) => slice.iter(),
-        _ => Default::default(),
-    };
+    // Timed games wake without input, to end when time runs out
+    let timeout = matches!(config.challenge, Challenge::Timed(_)).then_some(TICK_DURATION);
 
-    let io: Option<Io> = startup(input);
-    // Ensure the postcondition holds
-    assert_eq!(cfg!(not(fuzzing)), io.is_some());
-
-    // Clear screen and draw initial board
-    let mut generation: Generation = 0;
-    {
-        let mut unlocked = board.lock().unwrap();
-        for _ in 0..INITIAL_TILES_COUNT {
-            unlocked.spawn_tile(generation);
-        }
-    }
+    // Scripted input from a pipe or file plays a single game
+    let once = input.is_scripted();
 
     // Bookkeeping for board-drawing thread
     let draw_quit = Arc::new(atomic::AtomicBool::new(false));
@@ -118,13 +150,14 @@ pub fn play(input: &Input) -> Result<(), ()> {
         draw_thread = None;
     } else {
         // The usual case
-        assert!(cfg!(not(fuzzing)));
 
         // Spawn board-drawing thread
-        let board_arg = Arc::clone(&board);
+        let game_arg = Arc::clone(&game);
+        let layout_arg = config.layout.clone();
         let quit_arg = Arc::clone(&draw_quit);
         draw_join = Some(thread::spawn(move || {
-            board::draw(board_arg, &quit_arg).expect(constants::GAME_FAILURE_MESSAGE);
+            board::draw(&*game_arg, &layout_arg, output, &quit_arg)
+                .expect(constants::GAME_FAILURE_MESSAGE);
         }));
 
         // We need the thread handle separately
@@ -134,42 +167,42 @@ pub fn play(input: &Input) -> Result<(), ()> {
         draw_thread.as_ref().expect(EXPECT_NOT_FUZZING).unpark();
     }
 
-    let mut moved;
-    let mut empty_set = BTreeSet::new();
-
     // The main event loop
     loop {
-        generation += 1;
-        let action = match input {
-            Input::Slice(_) => Action::parse(*iter.next().unwrap_or(&END_OF_GAME_CHARACTER)),
-            Input::Interactive => stdin_reader(),
-        };
+        let key = input.read_key(timeout);
 
         // Read input and take action
-        match action {
-            Action::Direction(direction) => {
-                let mut unlocked = board.lock().unwrap();
-                moved = unlocked.update(direction, generation, &mut empty_set);
-
-                // If the move had no effect on a non-full board,
-                // skip adding a new tile
-                if !moved && unlocked.has_space() {
-                    continue;
-                }
-
-                // Add new starting tile if possible
-                // Has the player already used their last move?
-                if !unlocked.spawn_tile(generation) {
+        let step = {
+            let mut unlocked = game.lock().unwrap();
+            let action = key.map_or(Action::Continue, |key| unlocked.parse(key));
+            unlocked.act(action)
+        };
+        match step {
+            Step::Unchanged => continue,
+            Step::Changed => {}
+            Step::Over => {
+                record(finish(&mut game.lock().unwrap(), true));
+                if once {
                     break;
                 }
             }
-            Action::Continue => {
-                continue;
+            Step::NewGame | Step::Replay => {
+                let mut unlocked = game.lock().unwrap();
+                let records = finish(&mut unlocked, false);
+                let seed = if step == Step::Replay {
+                    unlocked.seed
+                } else {
+                    fastrand::u64(..)
+                };
+                *unlocked = Game::new(seed, config, unlocked.best);
+                drop(unlocked);
+                record(records);
             }
-            Action::Shutdown => {
+            Step::Quit => {
+                record(finish(&mut game.lock().unwrap(), false));
                 break;
             }
-        };
+        }
 
         // In case of update while not fuzzing, draw the board
         if cfg!(not(fuzzing)) {
@@ -182,21 +215,36 @@ pub fn play(input: &Input) -> Result<(), ()> {
         // Signal and join board-drawing thread, if it exists
         draw_quit.store(true, atomic::Ordering::Relaxed);
         draw_thread.as_ref().expect(EXPECT_NOT_FUZZING).unpark();
-        draw_join.unwrap().join().unwrap();
-
-        // Print end-of-game message
-        print!(
-            "{}{}",
-            board::constants::LEFT_SPACE,
-            board::constants::GAME_OVER
-        );
-
-        // Reset terminal
-        let io = io.expect("shutdown failed");
-        unsafe {
-            libc::tcsetattr(io.0, libc::TCSANOW, &io.1);
-        }
+        draw_join
+            .unwrap()
+            .join()
+            .map_err(|_| io::Error::other(constants::GAME_FAILURE_MESSAGE))?;
     }
 
     Ok(())
 }
+
+#[test]
+fn play_finish_test() {
+    use crate::{daily, date::Date};
+
+    let date = Date::parse("2026-10-18").unwrap();
+    let config = Config {
+        seed: Some(daily::seed(date)),
+        daily: Some(date),
+        ..Config::default()
+    };
+    let daily = |ended| {
+        let mut game = Game::new(daily::seed(date), &config, Best::default());
+        let direction = game.legal_moves().next().unwrap();
+        game.act(Action::Direction(direction));
+        match finish(&mut game, ended) {
+            Some(Records::Game(records)) => records.1,
+            _ => None,
+        }
+    };
+
+    // An abandoned daily game is recorded, but isn't the day's result
+    assert!(daily(false).is_none());
+    assert!(daily(true).is_some_and(|daily| daily.moves == 1));
+}
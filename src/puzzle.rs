@@ -0,0 +1,302 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::{
+    fs,
+    io::{self, Write},
+};
+
+use crate::{
+    board::{
+        constants::NUMBER_TILES_PER_LINE,
+        merge::{Classic, MergeRule},
+        Power, Rows,
+    },
+    store,
+};
+
+/// Puzzles for x800
+///
+/// Each puzzle starts from a given board, spawns a fixed sequence of
+/// tiles, and is solved by making the goal tile within a number of moves.
+/// Puzzles are written as lines of keywords, with '#' starting a comment:
+///
+/// ```text
+/// puzzle corner
+/// goal 256 4
+/// row 128 64 32 16
+/// row  .   .  .  16
+/// row  .   .  .   .
+/// row  *   .  .   .
+/// spawn 2 4@4,4
+/// ```
+///
+/// Rows use '.' for an empty cell and '*' for a rock. Spawns are tile
+/// values, optionally placed at a one-based row and column; otherwise,
+/// or when that cell is taken, the first empty cell is used. The
+/// sequence repeats once it runs out.
+const PACK: &str = include_str!("../puzzles/starter.txt");
+const FILE_NAME: &str = "puzzles";
+const EMPTY_CELL: &str = ".";
+const ROCK_CELL: &str = "*";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Puzzle {
+    pub name: String,
+    /// Value of the tile to make
+    pub goal: u32,
+    /// Moves allowed to make it
+    pub moves: u32,
+    pub board: Rows,
+    pub spawns: Vec<(Power, Option<(usize, usize)>)>,
+}
+
+impl Puzzle {
+    /// Parse every puzzle in a pack, or describe the first malformed line
+    pub fn parse_pack(text: &str) -> Result<Vec<Self>, String> {
+        let mut puzzles: Vec<Puzzle> = Vec::new();
+        let mut rows = 0;
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace();
+            let Some(keyword) = words.next() else {
+                continue;
+            };
+            let malformed = || format!("line {}: malformed '{}'", number + 1, line.trim());
+
+            if keyword == "puzzle" {
+                // Names are stored before '=' in the solutions file
+                let name = words
+                    .next()
+                    .filter(|name| !name.contains(|c: char| c == '=' || c.is_control()))
+                    .ok_or_else(malformed)?;
+                puzzles.push(Puzzle {
+                    name: name.to_string(),
+                    goal: 0,
+                    moves: 0,
+                    board: [[Some(0); NUMBER_TILES_PER_LINE]; NUMBER_TILES_PER_LINE],
+                    spawns: Vec::new(),
+                });
+                rows = 0;
+                continue;
+            }
+            let puzzle = puzzles.last_mut().ok_or_else(malformed)?;
+            match keyword {
+                "goal" => {
+                    puzzle.goal = words
+                        .next()
+                        .and_then(|goal| goal.parse().ok())
+                        .ok_or_else(malformed)?;
+                    puzzle.moves = words
+                        .next()
+                        .and_then(|moves| moves.parse().ok())
+                        .ok_or_else(malformed)?;
+                    Classic.level(puzzle.goal).ok_or_else(malformed)?;
+                }
+                "row" => {
+                    let row = puzzle.board.get_mut(rows).ok_or_else(malformed)?;
+                    for cell in row.iter_mut() {
+                        *cell = match words.next().ok_or_else(malformed)? {
+                            EMPTY_CELL => Some(0),
+                            ROCK_CELL => None,
+                            value => Some(
+                                value
+                                    .parse()
+                                    .ok()
+                                    .and_then(|value| Classic.level(value))
+                                    .ok_or_else(malformed)?,
+                            ),
+                        };
+                    }
+                    rows += 1;
+                }
+                "spawn" => {
+                    for spawn in words.by_ref() {
+                        puzzle
+                            .spawns
+                            .push(parse_spawn(spawn).ok_or_else(malformed)?);
+                    }
+                }
+                _ => return Err(malformed()),
+            }
+            if words.next().is_some() {
+                return Err(malformed());
+            }
+        }
+
+        match puzzles
+            .iter()
+            .find(|puzzle| puzzle.goal == 0 || puzzle.moves == 0 || puzzle.spawns.is_empty())
+        {
+            Some(puzzle) => Err(format!("puzzle '{}' needs a goal and spawns", puzzle.name)),
+            None => Ok(puzzles),
+        }
+    }
+
+    /// The bundled starter pack
+    pub fn pack() -> Vec<Self> {
+        Self::parse_pack(PACK).expect("Malformed puzzle pack")
+    }
+
+    /// A bundled puzzle by name, or else the first puzzle in a file
+    pub fn find(name: &str) -> Result<Self, String> {
+        if let Some(puzzle) = Self::pack().into_iter().find(|puzzle| puzzle.name == name) {
+            return Ok(puzzle);
+        }
+        let text = fs::read_to_string(name).map_err(|_| format!("unknown puzzle '{name}'"))?;
+        Self::parse_pack(&text)?
+            .into_iter()
+            .next()
+            .ok_or(format!("no puzzles in '{name}'"))
+    }
+
+    /// Level of the goal tile
+    pub fn target(&self) -> Power {
+        Classic.level(self.goal).unwrap_or_default()
+    }
+}
+
+fn parse_spawn(text: &str) -> Option<(Power, Option<(usize, usize)>)> {
+    let (value, cell) = match text.split_once('@') {
+        Some((value, cell)) => (value, Some(cell)),
+        None => (text, None),
+    };
+    let power = Classic.level(value.parse().ok()?)?;
+    let cell = match cell {
+        Some(cell) => {
+            let (row, column) = cell.split_once(',')?;
+            let (row, column): (usize, usize) = (row.parse().ok()?, column.parse().ok()?);
+            let range = 1..=NUMBER_TILES_PER_LINE;
+            (range.contains(&row) && range.contains(&column)).then_some((row - 1, column - 1))?
+        }
+        None => return Some((power, None)),
+    };
+    Some((power, Some(cell)))
+}
+
+/// Fewest moves taken to solve each puzzle, stored as `name=moves` lines
+fn solutions(text: &str) -> impl Iterator<Item = (&str, u32)> {
+    text.lines().filter_map(|line| {
+        let (name, moves) = line.split_once('=')?;
+        Some((name, moves.parse().ok()?))
+    })
+}
+
+/// Keep a solution, unless the puzzle was already solved in fewer moves
+pub fn record(name: &str, moves: u32) -> io::Result<()> {
+    store::update(FILE_NAME, |text| {
+        let mut best: Vec<(&str, u32)> = solutions(text)
+            .filter(|(other, _)| *other != name)
+            .collect();
+        let fewest = solutions(text)
+            .filter(|(other, _)| *other == name)
+            .map(|(_, moves)| moves)
+            .fold(moves, u32::min);
+        best.push((name, fewest));
+        let mut text = String::new();
+        for (name, moves) in best {
+            text.push_str(name);
+            text.push('=');
+            text.push_str(&moves.to_string());
+            text.push('\n');
+        }
+        text
+    })
+}
+
+/// Print the bundled pack, and which puzzles are solved
+pub fn list(output: &mut impl Write) -> io::Result<()> {
+    let text = store::read(FILE_NAME)?;
+    for (number, puzzle) in Puzzle::pack().iter().enumerate() {
+        let solved = solutions(&text)
+            .find(|(name, _)| *name == puzzle.name)
+            .map_or(String::from("unsolved"), |(_, moves)| {
+                format!("solved in {moves}")
+            });
+        writeln!(
+            output,
+            "{:>4}  {:<12} make {:<5} in {:>2} moves  {solved}",
+            number + 1,
+            puzzle.name,
+            puzzle.goal,
+            puzzle.moves
+        )?;
+    }
+    Ok(())
+}
+
+#[test]
+fn starter_pack_test() {
+    use crate::board::{Board, Direction};
+
+    // Whether the goal can be made from here, by trying every move
+    fn solvable(puzzle: &Puzzle, board: &Board, moves: u32) -> bool {
+        let directions = [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ];
+        moves != 0
+            && directions.into_iter().any(|direction| {
+                let mut next = board.clone();
+                if !next.update(direction, 1) {
+                    return false;
+                }
+                if next.max_tile() >= puzzle.target() {
+                    return true;
+                }
+                let taken = puzzle.moves - moves;
+                let (power, cell) = puzzle.spawns[taken as usize % puzzle.spawns.len()];
+                next.spawn_fixed(1, power, cell) && solvable(puzzle, &next, moves - 1)
+            })
+    }
+
+    let pack = Puzzle::pack();
+    assert!(pack.len() > 1);
+    for puzzle in &pack {
+        let board = Board::from_rows(puzzle.board);
+        assert!(solvable(puzzle, &board, puzzle.moves), "{}", puzzle.name);
+        assert!(
+            !solvable(puzzle, &board, puzzle.moves - 1),
+            "{}",
+            puzzle.name
+        );
+    }
+
+    assert_eq!(
+        Puzzle::parse_pack("puzzle a\ngoal 8 1\nrow 2 2 2\n"),
+        Err(String::from("line 3: malformed 'row 2 2 2'"))
+    );
+    assert!(Puzzle::parse_pack("puzzle a=b\ngoal 8 1\nspawn 2\n").is_err());
+
+    // Puzzles set their own rules, so refuse options which would change them
+    let config = |option: &str| {
+        let args = ["--puzzle=corner", option].map(String::from);
+        crate::config::Config::from_args(args.into_iter().filter(|arg| !arg.is_empty()))
+    };
+    assert!(config("").is_ok());
+    for option in ["--evil", "--rocks=1", "--spawn=hard", "--challenge=timed"] {
+        assert!(config(option).is_err(), "{option}");
+    }
+}
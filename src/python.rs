@@ -0,0 +1,184 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::{num::NonZeroUsize, thread};
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use crate::board::{merge::Classic, Direction, MergeRule, Tile};
+use crate::config::Config;
+use crate::env::{Env, Policy, Shaping};
+
+fn config(options: &str, seed: Option<u64>) -> PyResult<Config> {
+    let mut config = Config::from_args(options.split_whitespace().map(String::from))
+        .map_err(PyValueError::new_err)?;
+    config.seed = seed.or(config.seed);
+    Ok(config)
+}
+
+/// One game of x800, created from command-line options
+#[pyclass(name = "Game")]
+struct Game {
+    env: Env,
+}
+
+#[pymethods]
+impl Game {
+    #[new]
+    #[pyo3(signature = (options = "", seed = None))]
+    fn new(options: &str, seed: Option<u64>) -> PyResult<Self> {
+        let config = config(options, seed)?;
+        Ok(Game {
+            env: Env::new(&config, Shaping::default()),
+        })
+    }
+
+    /// Start a new game with tiles drawn from `seed`
+    fn reset(&mut self, seed: u64) {
+        self.env.reset(seed);
+    }
+
+    /// Rows of tile values, 0 when empty, None outside the board's shape,
+    /// or the name of a special tile
+    #[getter]
+    fn board(&self, py: Python<'_>) -> Vec<Vec<PyObject>> {
+        let rule = self.env.game.board.rule();
+        let cell = |tile| match tile {
+            Tile::Number(power, _) => rule.value(power).into_py(py),
+            Tile::Empty() => 0.into_py(py),
+            Tile::Rock() => "rock".into_py(py),
+            Tile::Joker() => "joker".into_py(py),
+            Tile::Bomb() => "bomb".into_py(py),
+            Tile::Double(power) => format!("x{}", Classic.value(power)).into_py(py),
+            Tile::Edge(_) | Tile::Corner(_) | Tile::Void() => py.None(),
+        };
+        let grid = self.env.game.board.grid();
+        grid.map(|row| row.iter().copied().map(cell).collect())
+            .collect()
+    }
+
+    /// Names of the moves of the board's topology
+    #[getter]
+    fn directions(&self) -> Vec<&'static str> {
+        self.env
+            .actions()
+            .iter()
+            .map(|direction| direction.name())
+            .collect()
+    }
+
+    /// Names of the moves which would change the board
+    fn legal_moves(&self) -> Vec<&'static str> {
+        let game = &self.env.game;
+        let legal = game.legal_moves().filter(|_| !game.over);
+        legal.map(Direction::name).collect()
+    }
+
+    /// Make a move by name, returning whether it changed the board
+    #[pyo3(name = "move")]
+    fn make_move(&mut self, direction: &str) -> PyResult<bool> {
+        let direction = Direction::parse(direction)
+            .ok_or_else(|| PyValueError::new_err(format!("unknown move {direction}")))?;
+        let topology = self.env.game.board.topology();
+        topology.check(direction).map_err(PyValueError::new_err)?;
+        let action = self
+            .env
+            .actions()
+            .iter()
+            .position(|&action| action == direction);
+        Ok(action.is_some_and(|action| self.env.step(action).3.changed))
+    }
+
+    #[getter]
+    fn score(&self) -> u32 {
+        self.env.game.board.score()
+    }
+
+    /// Number of moves which changed the board
+    #[getter]
+    fn moves(&self) -> u32 {
+        self.env.game.moves
+    }
+
+    /// Value of the highest tile
+    #[getter]
+    fn max_tile(&self) -> u32 {
+        self.env.info().max_tile
+    }
+
+    #[getter]
+    fn won(&self) -> bool {
+        self.env.game.has_won()
+    }
+
+    #[getter]
+    fn over(&self) -> bool {
+        self.env.game.over
+    }
+}
+
+/// Play `games` games to their ends natively, from seeds `seed`, `seed + 1`
+/// and so on, by the "random" or "greedy" policy. Games stop early after
+/// `max_moves` moves. Returns the score, moves and highest tile of each
+#[pyfunction]
+#[pyo3(signature = (games, seed = 0, options = "", policy = "random", max_moves = 100_000))]
+fn rollouts(
+    py: Python<'_>,
+    games: usize,
+    seed: u64,
+    options: &str,
+    policy: &str,
+    max_moves: u32,
+) -> PyResult<Vec<(u32, u32, u32)>> {
+    let policy = match policy {
+        "random" => Policy::Random,
+        "greedy" => Policy::Greedy,
+        _ => return Err(PyValueError::new_err(format!("unknown policy {policy}"))),
+    };
+    let env = Env::new(&config(options, Some(seed))?, Shaping::default());
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let mut results = vec![(0, 0, 0); games];
+
+    // Games are shared out between threads, outside the interpreter's lock
+    py.allow_threads(|| {
+        thread::scope(|scope| {
+            let chunk = games.div_ceil(threads).max(1);
+            for (i, results) in results.chunks_mut(chunk).enumerate() {
+                let mut env = env.clone();
+                let first = seed.wrapping_add((i * chunk) as u64);
+                scope.spawn(move || {
+                    for (index, result) in (0..).zip(results) {
+                        let info = env.rollout(first.wrapping_add(index), policy, max_moves);
+                        *result = (info.score, info.moves, info.max_tile);
+                    }
+                });
+            }
+        });
+    });
+    Ok(results)
+}
+
+#[pymodule]
+fn x800(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<Game>()?;
+    module.add_function(wrap_pyfunction!(rollouts, module)?)
+}
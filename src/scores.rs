@@ -0,0 +1,354 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    fmt,
+    io::{self, Write},
+};
+
+use crate::{
+    board::{
+        constants::NUMBER_TILES_PER_LINE,
+        merge::{self, Classic},
+        MergeRule, Rocks, SpawnRules, Topology,
+    },
+    challenge::Challenge,
+    date::Date,
+    game::{Game, Phase},
+    status::Clock,
+    store,
+};
+
+/// High-score table for x800
+///
+/// Stored as one game per line of space-separated `key=value` pairs, so
+/// that older tables keep loading as fields are added. Entries are kept
+/// sorted by score, and pruned separately within each category.
+const FILE_NAME: &str = "scores";
+const ENTRIES_PER_CATEGORY: usize = 100;
+const LISTED_PER_CATEGORY: usize = 10;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub score: u32,
+    /// Value of the largest tile, eg. 2048
+    pub tile: u32,
+    pub moves: u32,
+    pub seconds: u64,
+    pub seed: u64,
+    pub date: Date,
+    /// Value of the tile which wins the game
+    pub target: u32,
+    pub won: bool,
+    /// Whether the player kept going after winning
+    pub kept_going: bool,
+    pub spawn: SpawnRules,
+    pub evil: bool,
+    pub rocks: Rocks,
+    /// Name of the merge rule
+    pub merge: &'static str,
+    pub topology: Topology,
+    pub challenge: Challenge,
+}
+
+impl Entry {
+    pub fn from_game(game: &Game) -> Self {
+        let rule = game.board.rule();
+        Entry {
+            score: game.board.score(),
+            tile: rule.value(game.board.max_tile()),
+            moves: game.moves,
+            seconds: game.started.elapsed().as_secs(),
+            seed: game.seed,
+            date: Date::today(),
+            target: rule.value(game.target),
+            won: game.has_won(),
+            kept_going: game.phase == Phase::KeepGoing,
+            spawn: game.spawn.clone(),
+            evil: game.evil,
+            rocks: game.rocks,
+            merge: rule.name(),
+            topology: game.board.topology(),
+            challenge: game.challenge,
+        }
+    }
+
+    pub fn rule(&self) -> &'static dyn MergeRule {
+        merge::find(self.merge).unwrap_or(&Classic)
+    }
+
+    /// Only games within one category are ranked against each other
+    pub fn category(&self) -> String {
+        let rule = self.rule();
+        let mut category = if self.topology.is_square() {
+            format!("{NUMBER_TILES_PER_LINE}x{NUMBER_TILES_PER_LINE}")
+        } else {
+            self.topology.to_string()
+        };
+        if self.merge != Classic.name() {
+            category.push(' ');
+            category.push_str(self.merge);
+        }
+        if self.topology == Topology::Torus {
+            category.push_str(" torus");
+        }
+        if !self.spawn.is_default() {
+            category.push(' ');
+            category.push_str(&self.spawn.to_string());
+        }
+        if self.evil {
+            category.push_str(" evil");
+        }
+        if !self.rocks.is_none() {
+            category.push_str(" rocks ");
+            category.push_str(&self.rocks.to_string());
+        }
+        if !self.challenge.is_none() {
+            category.push(' ');
+            category.push_str(&self.challenge.to_string());
+        }
+        if self.target != rule.value(rule.target()) {
+            category.push_str(" to ");
+            category.push_str(&self.target.to_string());
+        }
+        category
+    }
+
+    pub fn parse(line: &str) -> Option<Self> {
+        if line.trim().is_empty() {
+            return None;
+        }
+
+        let mut entry = Entry {
+            score: 0,
+            tile: 0,
+            moves: 0,
+            seconds: 0,
+            seed: 0,
+            date: Date::from_days(0),
+            target: 0,
+            won: false,
+            kept_going: false,
+            spawn: SpawnRules::default(),
+            evil: false,
+            rocks: Rocks::default(),
+            merge: Classic.name(),
+            topology: Topology::Flat,
+            challenge: Challenge::None,
+        };
+        // Defaults depend on the merge rule, so are filled in last
+        let (mut target, mut spawn) = (None, None);
+
+        for pair in line.split_whitespace() {
+            let (key, value) = pair.split_once('=')?;
+            match key {
+                "score" => entry.score = value.parse().ok()?,
+                "tile" => entry.tile = value.parse().ok()?,
+                "moves" => entry.moves = value.parse().ok()?,
+                "seconds" => entry.seconds = value.parse().ok()?,
+                "seed" => entry.seed = u64::from_str_radix(value, 16).ok()?,
+                "date" => entry.date = Date::parse(value)?,
+                "target" => target = Some(value.parse().ok()?),
+                "won" => entry.won = value.parse().ok()?,
+                "kept_going" => entry.kept_going = value.parse().ok()?,
+                "spawn" => spawn = Some(value),
+                "evil" => entry.evil = value.parse().ok()?,
+                "rocks" => entry.rocks = Rocks::parse(value)?,
+                "merge" => entry.merge = merge::find(value)?.name(),
+                "topology" => entry.topology = Topology::parse(value)?,
+                "challenge" => entry.challenge = Challenge::parse(value)?,
+                // Written by a newer x800
+                _ => {}
+            }
+        }
+
+        let rule = entry.rule();
+        entry.target = target.unwrap_or_else(|| rule.value(rule.target()));
+        entry.spawn = match spawn {
+            Some(spawn) => SpawnRules::parse(spawn, rule)?,
+            None => SpawnRules::classic(rule),
+        };
+        Some(entry)
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "score={} tile={} moves={} seconds={} seed={:x} date={} \
+             target={} won={} kept_going={} spawn={} evil={} rocks={} merge={} \
+             topology={} challenge={}",
+            self.score,
+            self.tile,
+            self.moves,
+            self.seconds,
+            self.seed,
+            self.date,
+            self.target,
+            self.won,
+            self.kept_going,
+            self.spawn,
+            self.evil,
+            self.rocks,
+            self.merge,
+            self.topology,
+            self.challenge
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct Table(Vec<Entry>);
+
+impl Table {
+    /// Parse a table, skipping any malformed lines
+    pub fn parse(text: &str) -> Self {
+        Table(text.lines().filter_map(Entry::parse).collect())
+    }
+
+    pub fn load() -> io::Result<Self> {
+        store::read(FILE_NAME).map(|text| Self::parse(&text))
+    }
+
+    /// Add one game to the table on disk
+    pub fn record(entry: Entry) -> io::Result<()> {
+        store::update(FILE_NAME, |text| {
+            let mut table = Self::parse(text);
+            table.insert(entry);
+            table.to_string()
+        })
+    }
+
+    fn insert(&mut self, entry: Entry) {
+        self.0.push(entry);
+        // Races rank finishers by time, and everything else by score
+        self.0.sort_by_cached_key(|entry| {
+            let race = entry.challenge == Challenge::Race;
+            (
+                entry.category(),
+                Reverse(race && entry.won),
+                if race { entry.seconds } else { 0 },
+                Reverse(entry.score),
+                entry.moves,
+            )
+        });
+
+        // Keep the best few of each category
+        let mut counts = BTreeMap::new();
+        self.0.retain(|entry| {
+            let count = counts.entry(entry.category()).or_insert(0);
+            *count += 1;
+            *count <= ENTRIES_PER_CATEGORY
+        });
+    }
+
+    pub fn best(&self, category: &str) -> u32 {
+        self.0
+            .iter()
+            .find(|entry| entry.category() == category)
+            .map_or(0, |entry| entry.score)
+    }
+
+    /// Seconds taken by the fastest race won in a category
+    pub fn fastest(&self, category: &str) -> Option<u64> {
+        self.0
+            .iter()
+            .find(|entry| entry.category() == category)
+            .filter(|entry| entry.challenge == Challenge::Race && entry.won)
+            .map(|entry| entry.seconds)
+    }
+
+    /// Print the top entries of each category
+    pub fn list(&self, output: &mut impl Write) -> io::Result<()> {
+        let mut categories: BTreeMap<String, Vec<&Entry>> = BTreeMap::new();
+        for entry in &self.0 {
+            categories.entry(entry.category()).or_default().push(entry);
+        }
+
+        if categories.is_empty() {
+            writeln!(output, "no games recorded yet")?;
+        }
+        for (category, entries) in categories {
+            writeln!(output, "{category}")?;
+            writeln!(
+                output,
+                "{:>4} {:>8} {:>6} {:>6} {:>8}  {:<16}  date",
+                "#", "score", "tile", "moves", "time", "seed"
+            )?;
+            for (rank, entry) in entries.iter().take(LISTED_PER_CATEGORY).enumerate() {
+                writeln!(
+                    output,
+                    "{:>4} {:>8} {:>6} {:>6} {:>8}  {:016x}  {}",
+                    rank + 1,
+                    entry.score,
+                    entry.tile,
+                    entry.moves,
+                    Clock(entry.seconds).to_string(),
+                    entry.seed,
+                    entry.date
+                )?;
+            }
+            writeln!(output)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|entry| writeln!(f, "{entry}"))
+    }
+}
+
+#[test]
+fn table_round_trip_test() {
+    let line = "score=1024 tile=128 moves=90 seconds=61 seed=ff date=2026-10-18 \
+                target=2048 won=false kept_going=false spawn=classic evil=false \
+                rocks=0/0 merge=classic topology=flat challenge=none";
+    let mut table = Table::parse(&format!("{line}\nnot an entry\n"));
+    assert_eq!(table.to_string(), format!("{line}\n"));
+
+    let mut entry = table.0[0].clone();
+    entry.score = 2048;
+    table.insert(entry);
+    assert_eq!(table.best("4x4"), 2048);
+    assert_eq!(table.best("5x5"), 0);
+
+    // Older entries are classic, while others read tiles by their own rule
+    let entry = Entry::parse("score=8 tile=13 merge=fibonacci spawn=1:1").unwrap();
+    assert_eq!(entry.category(), "4x4 fibonacci 1:1");
+    assert_eq!(Entry::parse("score=8 tile=8 spawn=3:1"), None);
+    let entry = Entry::parse("score=8 size=4 topology=hex:2").unwrap();
+    assert_eq!(entry.category(), "hex:2");
+
+    // The fastest finished race ranks first
+    let mut races = Table::default();
+    for line in ["score=900 seconds=50", "score=500 seconds=40 won=true"] {
+        races.insert(Entry::parse(&format!("{line} challenge=race")).unwrap());
+    }
+    assert_eq!(races.best("4x4 race"), 500);
+    assert_eq!(races.fastest("4x4 race"), Some(40));
+    assert_eq!(table.fastest("4x4"), None);
+}
@@ -0,0 +1,266 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::{
+    fs,
+    io::{self, Read, Write},
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream},
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread, time,
+};
+
+use crate::config::Config;
+
+// A spectator who stops reading is dropped rather than kept forever
+const WRITE_TIMEOUT: time::Duration = time::Duration::from_millis(100);
+// Frames waiting for a slow spectator. Newer ones are skipped until it
+// catches up, so the game never waits
+const QUEUE_FRAMES: usize = 4;
+
+/// Frames queued for a thread writing to one spectator
+type Spectator = SyncSender<Arc<str>>;
+
+/// Where frames are published: a local TCP port, a TCP address, or
+/// otherwise the path of a Unix socket. The `tcp:` and `unix:` prefixes
+/// choose one outright
+enum Address<'a> {
+    Tcp(String),
+    Unix(&'a str),
+}
+
+impl<'a> Address<'a> {
+    fn parse(text: &'a str) -> Self {
+        let tcp = |address: &str| match address.parse::<u16>() {
+            Ok(port) => Address::Tcp(format!("127.0.0.1:{port}")),
+            Err(_) => Address::Tcp(address.to_string()),
+        };
+        if let Some(path) = text.strip_prefix("unix:") {
+            Address::Unix(path)
+        } else if let Some(address) = text.strip_prefix("tcp:") {
+            tcp(address)
+        } else if text.parse::<u16>().is_ok() || text.contains(':') && !Path::new(text).exists() {
+            tcp(text)
+        } else {
+            Address::Unix(text)
+        }
+    }
+}
+
+/// Where spectators are accepted, to wake the listening thread when the
+/// game ends
+enum Listening {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+/// Frames of a running game, fanned out to every spectator
+#[derive(Default)]
+struct Spectators {
+    streams: Vec<Spectator>,
+    /// The latest frame, shown at once to those who arrive between moves
+    last: Arc<str>,
+    closed: bool,
+}
+
+impl Spectators {
+    fn add(&mut self, mut stream: impl Write + Send + 'static) {
+        let (sender, frames) = mpsc::sync_channel::<Arc<str>>(QUEUE_FRAMES);
+        if sender.try_send(Arc::clone(&self.last)).is_err() {
+            return;
+        }
+        thread::spawn(move || {
+            for frame in frames {
+                if stream.write_all(frame.as_bytes()).is_err() {
+                    break;
+                }
+            }
+        });
+        self.streams.push(sender);
+    }
+
+    /// Accept spectators from `incoming` until the game ends
+    fn accept<S: Write + Send + 'static>(
+        spectators: &Mutex<Self>,
+        incoming: impl Iterator<Item = io::Result<S>>,
+        set_write_timeout: impl Fn(&S) -> io::Result<()>,
+    ) {
+        for stream in incoming.flatten() {
+            let mut spectators = spectators.lock().unwrap();
+            if spectators.closed {
+                break;
+            }
+            if set_write_timeout(&stream).is_ok() {
+                spectators.add(stream);
+            }
+        }
+    }
+}
+
+pub struct Publisher {
+    spectators: Arc<Mutex<Spectators>>,
+    listening: Listening,
+}
+
+impl Publisher {
+    /// Accept spectators at `address` from a thread of their own
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address can't be listened on
+    pub fn listen(address: &str) -> io::Result<Self> {
+        let spectators = Arc::new(Mutex::new(Spectators::default()));
+        let accepting = Arc::clone(&spectators);
+
+        let listening = match Address::parse(address) {
+            Address::Tcp(address) => {
+                let listener = TcpListener::bind(address)?;
+                let mut address = listener.local_addr()?;
+                if address.ip().is_unspecified() {
+                    address.set_ip(match address {
+                        SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                        SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+                    });
+                }
+                thread::spawn(move || {
+                    Spectators::accept(&accepting, listener.incoming(), |stream| {
+                        stream.set_write_timeout(Some(WRITE_TIMEOUT))
+                    });
+                });
+                Listening::Tcp(address)
+            }
+            Address::Unix(path) => {
+                // A socket left behind by an earlier game is replaced
+                if fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+                    fs::remove_file(path)?;
+                }
+                let listener = UnixListener::bind(path)?;
+                thread::spawn(move || {
+                    Spectators::accept(&accepting, listener.incoming(), |stream| {
+                        stream.set_write_timeout(Some(WRITE_TIMEOUT))
+                    });
+                });
+                Listening::Unix(PathBuf::from(path))
+            }
+        };
+        Ok(Publisher {
+            spectators,
+            listening,
+        })
+    }
+
+    /// Queue a frame for every spectator, dropping any who have gone.
+    /// Spectators who are behind skip the frame
+    pub fn publish(&self, frame: &str) {
+        let frame: Arc<str> = Arc::from(frame);
+        let mut spectators = self.spectators.lock().unwrap();
+        spectators.streams.retain(|stream| {
+            !matches!(
+                stream.try_send(Arc::clone(&frame)),
+                Err(TrySendError::Disconnected(_))
+            )
+        });
+        spectators.last = frame;
+    }
+}
+
+impl Drop for Publisher {
+    fn drop(&mut self) {
+        // Spectators are disconnected once their queued frames are written
+        let mut spectators = self.spectators.lock().unwrap();
+        spectators.closed = true;
+        spectators.streams.clear();
+        drop(spectators);
+
+        // Wake the listening thread, which then sees the game has ended
+        match &self.listening {
+            Listening::Tcp(address) => drop(TcpStream::connect(address)),
+            Listening::Unix(path) => {
+                drop(UnixStream::connect(path));
+                fs::remove_file(path).ok();
+            }
+        }
+    }
+}
+
+/// Show the frames of the game published at `config.address`, until it ends
+///
+/// # Errors
+///
+/// Returns an error if the game can't be reached
+pub fn watch(config: &Config) -> io::Result<()> {
+    let address = config.address.as_deref().unwrap_or_default();
+    let mut stream: Box<dyn Read> = match Address::parse(address) {
+        Address::Tcp(address) => Box::new(TcpStream::connect(address)?),
+        Address::Unix(path) => Box::new(UnixStream::connect(path)?),
+    };
+    let mut stdout = io::stdout().lock();
+    io::copy(&mut stream, &mut stdout)?;
+    writeln!(stdout)
+}
+
+#[test]
+fn spectate_test() {
+    let path = std::env::temp_dir().join(format!("x800-spectate-{}", std::process::id()));
+    let publisher = Publisher::listen(path.to_str().unwrap()).unwrap();
+    publisher.publish("first");
+
+    // Spectators arriving late see the latest frame, then each new one
+    let mut spectator = UnixStream::connect(&path).unwrap();
+    let mut frame = [0; 5];
+    spectator.read_exact(&mut frame).unwrap();
+    assert_eq!(&frame, b"first");
+    publisher.publish("again");
+    spectator.read_exact(&mut frame).unwrap();
+    assert_eq!(&frame, b"again");
+
+    // Spectators are disconnected when the game ends
+    drop(publisher);
+    assert!(!path.exists());
+    assert_eq!(spectator.read(&mut frame).unwrap(), 0);
+
+    // Socket paths may hold a colon, given a prefix or once they exist
+    assert!(matches!(Address::parse("7000"), Address::Tcp(address) if address == "127.0.0.1:7000"));
+    assert!(
+        matches!(Address::parse("tcp:7000"), Address::Tcp(address) if address == "127.0.0.1:7000")
+    );
+    assert!(matches!(Address::parse("tcp:x800"), Address::Tcp(address) if address == "x800"));
+    assert!(matches!(Address::parse("host:7000"), Address::Tcp(_)));
+    assert!(matches!(
+        Address::parse("unix:run/x800:1.sock"),
+        Address::Unix("run/x800:1.sock")
+    ));
+    let colon = path.with_file_name(format!("x800:{}", std::process::id()));
+    let publisher = Publisher::listen(&format!("unix:{}", colon.display())).unwrap();
+    assert!(matches!(
+        Address::parse(colon.to_str().unwrap()),
+        Address::Unix(_)
+    ));
+    drop(publisher);
+}
@@ -0,0 +1,233 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write},
+    io,
+};
+
+use crate::{
+    board::{
+        constants::LEFT_SPACE,
+        constants::MAX_SPECIAL_POWER,
+        constants::STATUS_WIDTH,
+        merge::{Classic, MergeRule},
+        Power,
+    },
+    colour::Colour,
+    scores::Entry,
+    status::Clock,
+    store,
+};
+
+/// Lifetime statistics for x800
+///
+/// Aggregated over every finished game, and stored as `key=value` lines
+/// next to the high-score table.
+const FILE_NAME: &str = "stats";
+const HISTOGRAM_WIDTH: u32 = 12;
+const TILE_KEY_PREFIX: &str = "tile.";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub games: u32,
+    pub wins: u32,
+    /// Wins where the player kept going after reaching the target
+    pub kept_going: u32,
+    pub total_score: u64,
+    pub most_moves: u32,
+    pub longest_seconds: u64,
+    pub streak: u32,
+    pub best_streak: u32,
+    /// Number of classic games ending with each largest tile power
+    pub tiles: BTreeMap<Power, u32>,
+}
+
+impl Stats {
+    /// Parse statistics, skipping any malformed or unknown lines
+    pub fn parse(text: &str) -> Self {
+        let mut stats = Stats::default();
+
+        for (key, value) in text.lines().filter_map(|line| line.split_once('=')) {
+            let Ok(value) = value.trim().parse::<u64>() else {
+                continue;
+            };
+            let small = u32::try_from(value).unwrap_or(u32::MAX);
+            match key {
+                "games" => stats.games = small,
+                "wins" => stats.wins = small,
+                "kept_going" => stats.kept_going = small,
+                "total_score" => stats.total_score = value,
+                "most_moves" => stats.most_moves = small,
+                "longest_seconds" => stats.longest_seconds = value,
+                "streak" => stats.streak = small,
+                "best_streak" => stats.best_streak = small,
+                _ => {
+                    // No game ends with a tile past the largest there can be
+                    let power = key.strip_prefix(TILE_KEY_PREFIX).map(str::parse);
+                    if let Some(Ok(power @ ..=MAX_SPECIAL_POWER)) = power {
+                        if small != 0 {
+                            stats.tiles.insert(power, small);
+                        }
+                    }
+                }
+            }
+        }
+        stats
+    }
+
+    pub fn load() -> io::Result<Self> {
+        store::read(FILE_NAME).map(|text| Self::parse(&text))
+    }
+
+    /// Add one game to the statistics on disk
+    pub fn record(entry: &Entry) -> io::Result<()> {
+        store::update(FILE_NAME, |text| {
+            let mut stats = Self::parse(text);
+            stats.add(entry);
+            stats.to_string()
+        })
+    }
+
+    fn add(&mut self, entry: &Entry) {
+        self.games += 1;
+        self.total_score += u64::from(entry.score);
+        self.most_moves = self.most_moves.max(entry.moves);
+        self.longest_seconds = self.longest_seconds.max(entry.seconds);
+        if entry.merge == Classic.name() {
+            *self.tiles.entry(entry.tile.trailing_zeros()).or_default() += 1;
+        }
+
+        if entry.won {
+            self.wins += 1;
+            self.kept_going += u32::from(entry.kept_going);
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+        } else {
+            self.streak = 0;
+        }
+    }
+
+    /// Draw the statistics screen, using the board's palette
+    pub fn draw(&self, buffer: &mut String) -> fmt::Result {
+        let top = self.tiles.keys().next_back().copied().unwrap_or(0);
+        let no_colour = Colour::default();
+        let average = self
+            .total_score
+            .checked_div(u64::from(self.games))
+            .unwrap_or(0);
+        let win_rate = (u64::from(self.wins) * 100)
+            .checked_div(u64::from(self.games))
+            .unwrap_or(0);
+
+        // Header bar in the colour of the best tile so far
+        write!(
+            buffer,
+            "{LEFT_SPACE}{}{:<STATUS_WIDTH$}{no_colour}\r\n\n",
+            Colour::from_power(top),
+            " lifetime statistics"
+        )?;
+
+        for (label, value) in [
+            ("games played", self.games.to_string()),
+            ("games won", format!("{} ({win_rate}%)", self.wins)),
+            ("kept going", self.kept_going.to_string()),
+            ("average score", average.to_string()),
+            ("most moves", self.most_moves.to_string()),
+            ("longest game", Clock(self.longest_seconds).to_string()),
+            ("win streak", self.streak.to_string()),
+            ("best win streak", self.best_streak.to_string()),
+        ] {
+            write!(buffer, "{LEFT_SPACE}{label:<16}{value:>9}\r\n")?;
+        }
+
+        // Histogram of the largest tile of each game
+        write!(buffer, "\r\n{LEFT_SPACE}largest tile\r\n")?;
+        let most = u64::from(self.tiles.values().copied().max().unwrap_or(0));
+        for (&power, &count) in self.tiles.iter().filter(|_| most != 0) {
+            let colour = Colour::from_power(power);
+            let width = (u64::from(count) * u64::from(HISTOGRAM_WIDTH)).div_ceil(most);
+            let width = usize::try_from(width).unwrap_or(0);
+            let value = 1_u64.checked_shl(power).unwrap_or(0);
+            write!(
+                buffer,
+                "{LEFT_SPACE}{colour}{value:^6}{no_colour} {colour}{:width$}{no_colour} {count}\r\n",
+                "",
+            )?;
+        }
+        write!(buffer, "\r\n")
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "games={}", self.games)?;
+        writeln!(f, "wins={}", self.wins)?;
+        writeln!(f, "kept_going={}", self.kept_going)?;
+        writeln!(f, "total_score={}", self.total_score)?;
+        writeln!(f, "most_moves={}", self.most_moves)?;
+        writeln!(f, "longest_seconds={}", self.longest_seconds)?;
+        writeln!(f, "streak={}", self.streak)?;
+        writeln!(f, "best_streak={}", self.best_streak)?;
+        self.tiles
+            .iter()
+            .try_for_each(|(power, count)| writeln!(f, "{TILE_KEY_PREFIX}{power}={count}"))
+    }
+}
+
+#[test]
+fn stats_add_test() {
+    let entry = |tile, score, won: bool, kept_going: bool| {
+        Entry::parse(&format!(
+            "score={score} tile={tile} moves=10 seconds=5 won={won} kept_going={kept_going}"
+        ))
+        .unwrap()
+    };
+    let mut stats = Stats::default();
+    stats.add(&entry(2048, 20_000, true, false));
+    stats.add(&entry(4096, 40_000, true, true));
+    stats.add(&entry(256, 3_000, false, false));
+
+    assert_eq!(stats.games, 3);
+    assert_eq!(stats.wins, 2);
+    assert_eq!(stats.kept_going, 1);
+    assert_eq!(stats.streak, 0);
+    assert_eq!(stats.best_streak, 2);
+    assert_eq!(stats.tiles.get(&11), Some(&1));
+    assert_eq!(Stats::parse(&stats.to_string()), stats);
+
+    // Hand-edited counts which no game could leave are dropped, and the rest
+    // still draw
+    let mut buffer = String::new();
+    for text in [
+        "tile.11=0",
+        "tile.64=1",
+        "tile.32=1\ntile.5=4294967295\ntile.6=1",
+    ] {
+        let stats = Stats::parse(text);
+        assert!(stats.tiles.keys().all(|&power| power <= MAX_SPECIAL_POWER));
+        assert!(stats.tiles.values().all(|&count| count != 0));
+        stats.draw(&mut buffer).unwrap();
+    }
+    assert!(buffer.contains(" 4294967295\r\n"));
+}
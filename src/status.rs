@@ -0,0 +1,213 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::{
+    fmt::{self, Write},
+    time,
+};
+
+use crate::board::constants::{
+    GAME_OVER_PROMPT, LEFT_SPACE, ONLINE_OVER_PROMPT, PUZZLE_FAILED_PROMPT, PUZZLE_SOLVED_PROMPT,
+    STATUS_WIDTH, WIN_MESSAGE, WIN_PROMPT,
+};
+use crate::board::Topology;
+use crate::challenge::Challenge;
+use crate::colour::Colour;
+use crate::game::{Game, Phase};
+
+/// Status area for x800
+///
+/// Drawn beneath the board. The layout is written as field names,
+/// separated by ',' within a line and by '/' between lines. For example,
+/// "score,moves/best,record" draws two lines of two fields each.
+pub const DEFAULT_LAYOUT: &str = "score,moves/best,record/time/seed/keys";
+
+/// How often the clock in the status area ticks, and timed games check it
+pub const TICK_DURATION: time::Duration = time::Duration::from_millis(250);
+
+const FIELD_SEPARATOR: char = ',';
+const LINE_SEPARATOR: char = '/';
+const FIELD_SPACE: &str = "  ";
+const KEY_HINTS: &str = "wasd move  n new  ^c quit";
+const HEX_KEY_HINTS: &str = "qweasd move  n new  ^c quit";
+const CUBE_KEY_HINTS: &str = "wasd move  qe layer  ^c quit";
+const PLAYER_TWO_KEY_HINTS: &str = "ijkl move  n new  ^c quit";
+const ONLINE_KEY_HINTS: &str = "wasd move  ^c quit";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    Score,
+    Moves,
+    Best,
+    Record,
+    Time,
+    Seed,
+    Keys,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        match name.trim() {
+            "score" => Some(Field::Score),
+            "moves" => Some(Field::Moves),
+            "best" => Some(Field::Best),
+            "record" => Some(Field::Record),
+            "time" => Some(Field::Time),
+            "seed" => Some(Field::Seed),
+            "keys" => Some(Field::Keys),
+            _ => None,
+        }
+    }
+
+    fn draw(self, game: &Game, buffer: &mut String) -> fmt::Result {
+        match self {
+            Field::Score => write!(buffer, "score {}", game.board.score()),
+            Field::Moves => match game.challenge.moves_left(game.moves) {
+                Some(left) => write!(buffer, "moves left {left}"),
+                None => write!(buffer, "moves {}", game.moves),
+            },
+            // Races are ranked by time
+            Field::Best if game.challenge == Challenge::Race => {
+                write!(buffer, "best {}", Fastest(game.best.session_seconds))
+            }
+            Field::Record if game.challenge == Challenge::Race => {
+                write!(buffer, "record {}", Fastest(game.best.all_time_seconds))
+            }
+            Field::Best => write!(buffer, "best {}", game.best.session),
+            Field::Record => write!(buffer, "record {}", game.best.all_time),
+            Field::Time => match game.time_left() {
+                Some(left) => write!(buffer, "time left {}", Clock(left)),
+                None => write!(buffer, "time {}", Clock(game.started.elapsed().as_secs())),
+            },
+            Field::Seed => write!(buffer, "seed {:016x}", game.seed),
+            Field::Keys if game.player == Some(1) => write!(buffer, "{PLAYER_TWO_KEY_HINTS}"),
+            Field::Keys if game.online => write!(buffer, "{ONLINE_KEY_HINTS}"),
+            Field::Keys => match game.board.topology() {
+                Topology::Flat | Topology::Torus => write!(buffer, "{KEY_HINTS}"),
+                Topology::Hex(_) => write!(buffer, "{HEX_KEY_HINTS}"),
+                Topology::Cube => write!(buffer, "{CUBE_KEY_HINTS}"),
+            },
+        }
+    }
+}
+
+/// A duration in whole seconds, shown as minutes and seconds
+pub struct Clock(pub u64);
+
+impl fmt::Display for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{:02}", self.0 / 60, self.0 % 60)
+    }
+}
+
+/// The time of the fastest race won, if any
+struct Fastest(Option<u64>);
+
+impl fmt::Display for Fastest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(seconds) => write!(f, "{}", Clock(seconds)),
+            None => write!(f, "-:--"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout(Vec<Vec<Field>>);
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::parse(DEFAULT_LAYOUT).expect("Malformed default layout")
+    }
+}
+
+impl Layout {
+    /// Parse a layout, or return None on any unknown field name
+    pub fn parse(text: &str) -> Option<Self> {
+        text.split(LINE_SEPARATOR)
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.split(FIELD_SEPARATOR).map(Field::parse).collect())
+            .collect::<Option<_>>()
+            .map(Layout)
+    }
+
+    /// The elapsed time is the only field which changes without input
+    pub fn is_ticking(&self) -> bool {
+        self.0.iter().flatten().any(|field| *field == Field::Time)
+    }
+
+    pub fn draw(&self, game: &Game, buffer: &mut String) -> fmt::Result {
+        // The first line takes the colour of the largest tile
+        let mut colour = Colour::from_power(game.board.max_tile());
+        let mut line = String::with_capacity(STATUS_WIDTH);
+
+        for fields in &self.0 {
+            line.clear();
+            for (i, field) in fields.iter().enumerate() {
+                if i != 0 {
+                    line.push_str(FIELD_SPACE);
+                }
+                field.draw(game, &mut line)?;
+            }
+            write!(
+                buffer,
+                "{LEFT_SPACE}{colour}{line:<STATUS_WIDTH$}{}\r\n",
+                Colour::default()
+            )?;
+            colour = Colour::default();
+        }
+        Ok(())
+    }
+}
+
+/// Line between the board and the status area
+pub fn draw_banner(game: &Game, buffer: &mut String) -> fmt::Result {
+    let banner = match game.phase {
+        _ if game.over && game.puzzle.is_some() => {
+            if game.is_solved() {
+                PUZZLE_SOLVED_PROMPT
+            } else {
+                PUZZLE_FAILED_PROMPT
+            }
+        }
+        _ if game.over && game.online => ONLINE_OVER_PROMPT,
+        _ if game.over => GAME_OVER_PROMPT,
+        Phase::Playing => "\r\n",
+        Phase::Won => WIN_PROMPT,
+        Phase::KeepGoing => WIN_MESSAGE,
+    };
+    write!(buffer, "{LEFT_SPACE}{banner}")
+}
+
+#[test]
+fn layout_parse_test() {
+    assert_eq!(
+        Layout::parse("score,moves/time"),
+        Some(Layout(vec![
+            vec![Field::Score, Field::Moves],
+            vec![Field::Time]
+        ]))
+    );
+    assert_eq!(Layout::parse("score,bogus"), None);
+    assert!(Layout::default().is_ticking());
+    assert!(!Layout::parse("seed").unwrap().is_ticking());
+}
@@ -0,0 +1,95 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, Write},
+    os::fd::AsRawFd,
+    path::PathBuf,
+    process,
+};
+
+/// Local storage for x800
+///
+/// Files live under `$XDG_DATA_HOME/x800`, falling back to
+/// `$HOME/.local/share/x800` when the former is unset.
+///
+/// Each file is replaced atomically by renaming a complete temporary
+/// copy over it. Writers hold an exclusive `flock(2)` on a sibling lock
+/// file for the whole read-modify-write, so concurrent games can't lose
+/// each other's updates.
+const DIRECTORY_NAME: &str = "x800";
+const LOCK_SUFFIX: &str = ".lock";
+
+pub fn data_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_DATA_HOME") {
+        Some(path) if !path.is_empty() => PathBuf::from(path),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".local/share"),
+    };
+    Some(base.join(DIRECTORY_NAME))
+}
+
+fn path(name: &str) -> io::Result<PathBuf> {
+    data_dir()
+        .map(|dir| dir.join(name))
+        .ok_or(io::ErrorKind::NotFound.into())
+}
+
+/// Read a data file, where a missing file reads as empty
+pub fn read(name: &str) -> io::Result<String> {
+    match fs::read_to_string(path(name)?) {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        result => result,
+    }
+}
+
+/// Replace a data file with the result of `change`, under lock
+pub fn update(name: &str, change: impl FnOnce(&str) -> String) -> io::Result<()> {
+    let target = path(name)?;
+    let dir = target.parent().expect("Malformed data path");
+    fs::create_dir_all(dir)?;
+
+    // Released when the file is closed
+    let lock = File::create(dir.join(format!("{name}{LOCK_SUFFIX}")))?;
+    if unsafe { libc::flock(lock.as_raw_fd(), libc::LOCK_EX) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let contents = change(&read(name)?);
+
+    let temporary = dir.join(format!(".{name}.{}", process::id()));
+    let result = File::create(&temporary)
+        .and_then(|mut file| {
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temporary, &target));
+
+    // Don't leave a partial copy behind
+    if result.is_err() {
+        fs::remove_file(&temporary).ok();
+    }
+
+    drop(lock);
+    result
+}
@@ -0,0 +1,270 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::{
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream},
+    slice,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread, time,
+};
+
+use crate::board::{constants::END_OF_GAME_CHARACTER, Window};
+use crate::config::Config;
+use crate::play::play;
+use crate::terminal::{FrameSink, InputSource};
+
+// Telnet commands and options, from RFC 854 onwards
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const INTERRUPT: u8 = 244;
+const SE: u8 = 240;
+const ECHO: u8 = 1;
+const SUPPRESS_GO_AHEAD: u8 = 3;
+const NAWS: u8 = 31;
+
+// The server echoes nothing, and keys are sent as they're pressed. The
+// client reports its window size, and again whenever it changes
+const NEGOTIATION: [u8; 9] = [IAC, WILL, ECHO, IAC, WILL, SUPPRESS_GO_AHEAD, IAC, DO, NAWS];
+
+// A player who stops reading is skipped rather than stalling their game
+const WRITE_TIMEOUT: time::Duration = time::Duration::from_secs(1);
+// A player who presses no key for this long is disconnected
+const IDLE_TIMEOUT: time::Duration = time::Duration::from_mins(10);
+// Games served at once. Anyone connecting beyond this is turned away
+const MAX_SESSIONS: usize = 64;
+
+#[derive(Clone, Copy, Default)]
+enum State {
+    #[default]
+    Data,
+    /// After IAC
+    Command,
+    /// After WILL, WONT, DO or DONT, awaiting the option
+    Option,
+    /// Bytes read of a subnegotiation, including its option
+    Subnegotiation(usize),
+    /// After IAC within a subnegotiation
+    SubnegotiationCommand(usize),
+}
+
+/// Separates keys from the telnet commands among them
+#[derive(Default)]
+struct Parser {
+    state: State,
+    /// Option and parameters of the latest subnegotiation
+    subnegotiation: [u8; 5],
+}
+
+impl Parser {
+    /// The key in `byte`, if it completes one
+    fn parse(&mut self, byte: u8, window: &Window) -> Option<u8> {
+        let (state, key) = match (self.state, byte) {
+            (State::Data, IAC) => (State::Command, None),
+            (State::Data, _) => (State::Data, Some(byte)),
+            (State::Command, IAC) => (State::Data, Some(IAC)),
+            (State::Command, INTERRUPT) => (State::Data, Some(END_OF_GAME_CHARACTER)),
+            (State::Command, WILL | WONT | DO | DONT) => (State::Option, None),
+            (State::Command, SB) => (State::Subnegotiation(0), None),
+            (State::Subnegotiation(read), IAC) => (State::SubnegotiationCommand(read), None),
+            (State::Subnegotiation(read), _) | (State::SubnegotiationCommand(read), IAC) => {
+                if let Some(slot) = self.subnegotiation.get_mut(read) {
+                    *slot = byte;
+                }
+                (State::Subnegotiation(read + 1), None)
+            }
+            (State::SubnegotiationCommand(read), SE) => {
+                if let (5, [NAWS, columns, columns_low, rows, rows_low]) =
+                    (read, self.subnegotiation)
+                {
+                    window.resize(
+                        u16::from_be_bytes([columns, columns_low]),
+                        u16::from_be_bytes([rows, rows_low]),
+                    );
+                }
+                (State::Data, None)
+            }
+            (State::Command | State::Option | State::SubnegotiationCommand(_), _) => {
+                (State::Data, None)
+            }
+        };
+        self.state = state;
+        key
+    }
+}
+
+/// A player's terminal, at the other end of the connection
+struct Connection {
+    stream: TcpStream,
+    window: Arc<Window>,
+}
+
+impl FrameSink for Connection {
+    fn write_frame(&mut self, frame: &str) -> io::Result<()> {
+        self.stream.write_frame(frame)
+    }
+
+    fn window(&self) -> Option<Arc<Window>> {
+        Some(Arc::clone(&self.window))
+    }
+}
+
+/// One player's game over telnet
+pub struct Session {
+    stream: TcpStream,
+    window: Arc<Window>,
+    parser: Parser,
+    last_key: time::Instant,
+}
+
+impl Session {
+    fn open(mut stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        stream.write_all(&NEGOTIATION)?;
+        Ok(Session {
+            stream,
+            window: Arc::default(),
+            parser: Parser::default(),
+            last_key: time::Instant::now(),
+        })
+    }
+
+    /// Frames for the player's terminal
+    fn sink(&self) -> io::Result<Connection> {
+        Ok(Connection {
+            stream: self.stream.try_clone()?,
+            window: Arc::clone(&self.window),
+        })
+    }
+}
+
+impl InputSource for Session {
+    fn read_key(&mut self, timeout: Option<time::Duration>) -> Option<u8> {
+        let idle = IDLE_TIMEOUT.saturating_sub(self.last_key.elapsed());
+        if idle.is_zero() {
+            return Some(END_OF_GAME_CHARACTER);
+        }
+        let timeout = timeout.map_or(idle, |timeout| timeout.min(idle));
+        if self.stream.set_read_timeout(Some(timeout)).is_err() {
+            return Some(END_OF_GAME_CHARACTER);
+        }
+        loop {
+            let mut byte = 0_u8;
+            match self.stream.read(slice::from_mut(&mut byte)) {
+                Ok(1) => {}
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    return None;
+                }
+                _ => return Some(END_OF_GAME_CHARACTER),
+            }
+            if let Some(key) = self.parser.parse(byte, &self.window) {
+                self.last_key = time::Instant::now();
+                return Some(key);
+            }
+        }
+    }
+}
+
+/// A place among the games being served, given up when its game ends
+struct Seat(Arc<AtomicUsize>);
+
+impl Seat {
+    fn take(sessions: &Arc<AtomicUsize>) -> Option<Self> {
+        sessions
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |taken| {
+                (taken < MAX_SESSIONS).then_some(taken + 1)
+            })
+            .ok()
+            .map(|_| Seat(Arc::clone(sessions)))
+    }
+}
+
+impl Drop for Seat {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Serve separate games to everyone who connects with telnet or nc
+///
+/// # Errors
+///
+/// Returns an error if the port can't be listened on
+pub fn serve(config: &Config) -> io::Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", config.port))?;
+    println!("serving x800 on port {}", config.port);
+    let sessions = Arc::new(AtomicUsize::new(0));
+    for mut stream in listener.incoming().flatten() {
+        let Some(seat) = Seat::take(&sessions) else {
+            stream.write_all(b"x800 is full, try again later\r\n").ok();
+            continue;
+        };
+        let config = config.clone();
+        thread::spawn(move || {
+            let _seat = seat;
+            if let Ok(mut session) = Session::open(stream) {
+                if let Ok(sink) = session.sink() {
+                    play(&mut session, Box::new(sink), &config).ok();
+                }
+            }
+        });
+    }
+    Ok(())
+}
+
+#[test]
+fn telnet_parse_test() {
+    let window = Window::default();
+    let mut parser = Parser::default();
+    let mut keys = |bytes: &[u8]| -> Vec<u8> {
+        bytes
+            .iter()
+            .filter_map(|&byte| parser.parse(byte, &window))
+            .collect()
+    };
+
+    // Negotiation is skipped, and an escaped IAC is a key
+    assert_eq!(
+        keys(&[b'w', IAC, DO, ECHO, b'a', IAC, IAC]),
+        [b'w', b'a', IAC]
+    );
+    assert_eq!(keys(&[IAC, INTERRUPT]), [END_OF_GAME_CHARACTER]);
+
+    // Window sizes arrive in a subnegotiation, where IAC is escaped
+    assert_eq!(keys(&[IAC, SB, NAWS, 0, 80, 0, 24, IAC, SE, b's']), [b's']);
+    assert_eq!(window.size(), (80, 24));
+    assert!(keys(&[IAC, SB, NAWS, 1, IAC, IAC, 0, 50, IAC, SE]).is_empty());
+    assert_eq!(window.size(), (511, 50));
+}
@@ -0,0 +1,336 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    mem,
+    net::TcpStream,
+    os::fd::{AsRawFd, FromRawFd, RawFd},
+    ptr, slice,
+    sync::{Arc, Mutex},
+    time,
+};
+
+use crate::board::{constants::END_OF_GAME_CHARACTER, Window};
+
+/// Where a game's keys come from
+pub trait InputSource {
+    /// Read one key, or give up with None after `timeout`. Once there are
+    /// no more keys, this is `END_OF_GAME_CHARACTER`
+    fn read_key(&mut self, timeout: Option<time::Duration>) -> Option<u8>;
+
+    /// Keys come from a script rather than a player, so one game is played
+    fn is_scripted(&self) -> bool {
+        false
+    }
+
+    /// Finished games go in this machine's high-score table and statistics
+    fn keeps_records(&self) -> bool {
+        false
+    }
+}
+
+/// Where a game's frames are drawn
+pub trait FrameSink: Send {
+    /// Write one frame. Each redraws the whole screen, except the farewell
+    /// after the last
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the frame can't be written
+    fn write_frame(&mut self, frame: &str) -> io::Result<()>;
+
+    /// The window frames are shown in, for sinks which know its size
+    fn window(&self) -> Option<Arc<Window>> {
+        None
+    }
+}
+
+/// Read one byte from `fd`, or give up with None after `timeout`
+///
+/// Reads are unbuffered, so that waiting never misses input already read
+fn read_fd(fd: RawFd, timeout: Option<time::Duration>) -> Option<u8> {
+    if let Some(timeout) = timeout {
+        let mut poll = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let millis = libc::c_int::try_from(timeout.as_millis()).unwrap_or(libc::c_int::MAX);
+        if unsafe { libc::poll(&raw mut poll, 1, millis) } == 0 {
+            return None;
+        }
+    }
+
+    let mut byte = 0_u8;
+    if unsafe { libc::read(fd, (&raw mut byte).cast(), 1) } != 1 {
+        return Some(END_OF_GAME_CHARACTER);
+    }
+    Some(byte)
+}
+
+/// Put the terminal at `fd` in raw mode, returning its mode before
+fn make_raw(fd: RawFd) -> Option<libc::termios> {
+    unsafe {
+        let mut ios: libc::termios = mem::zeroed();
+        if libc::tcgetattr(fd, &raw mut ios) != 0 {
+            return None;
+        }
+        let original = ios;
+        libc::cfmakeraw(&raw mut ios);
+        libc::tcsetattr(fd, libc::TCSANOW, &raw const ios);
+        Some(original)
+    }
+}
+
+/// The process's own terminal, in raw mode until dropped
+pub struct Tty(Option<libc::termios>);
+
+impl Tty {
+    #[must_use]
+    pub fn open() -> Self {
+        Tty(make_raw(libc::STDIN_FILENO))
+    }
+}
+
+impl Drop for Tty {
+    /// Put the terminal back as it was found
+    fn drop(&mut self) {
+        if let Some(ios) = &self.0 {
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, ios);
+            }
+        }
+    }
+}
+
+impl InputSource for Tty {
+    fn read_key(&mut self, timeout: Option<time::Duration>) -> Option<u8> {
+        read_fd(libc::STDIN_FILENO, timeout)
+    }
+
+    /// Keys piped from a file or another program
+    fn is_scripted(&self) -> bool {
+        unsafe { libc::isatty(libc::STDIN_FILENO) == 0 }
+    }
+
+    fn keeps_records(&self) -> bool {
+        true
+    }
+}
+
+impl FrameSink for io::Stdout {
+    fn write_frame(&mut self, frame: &str) -> io::Result<()> {
+        self.write_all(frame.as_bytes())?;
+        self.flush()
+    }
+}
+
+/// Keys from memory, then the end of the game
+pub struct Memory<'a>(slice::Iter<'a, u8>);
+
+impl<'a> Memory<'a> {
+    #[must_use]
+    pub fn new(keys: &'a [u8]) -> Self {
+        Memory(keys.iter())
+    }
+}
+
+impl InputSource for Memory<'_> {
+    fn read_key(&mut self, _: Option<time::Duration>) -> Option<u8> {
+        Some(*self.0.next().unwrap_or(&END_OF_GAME_CHARACTER))
+    }
+}
+
+/// Frames kept in memory, shared with whoever reads them
+#[derive(Clone, Default)]
+pub struct Frames(Arc<Mutex<Vec<String>>>);
+
+impl Frames {
+    /// Frames drawn since last taken
+    ///
+    /// # Panics
+    ///
+    /// Panics if a drawing thread panicked while holding the frames
+    #[must_use]
+    pub fn take(&self) -> Vec<String> {
+        mem::take(&mut self.0.lock().unwrap())
+    }
+}
+
+impl FrameSink for Frames {
+    fn write_frame(&mut self, frame: &str) -> io::Result<()> {
+        self.0.lock().unwrap().push(frame.to_string());
+        Ok(())
+    }
+}
+
+/// A socket, which plays without any telnet negotiation
+impl InputSource for TcpStream {
+    fn read_key(&mut self, timeout: Option<time::Duration>) -> Option<u8> {
+        if self.set_read_timeout(timeout).is_err() {
+            return Some(END_OF_GAME_CHARACTER);
+        }
+        let mut byte = 0_u8;
+        match self.read(slice::from_mut(&mut byte)) {
+            Ok(1) => Some(byte),
+            Err(error)
+                if matches!(
+                    error.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                None
+            }
+            _ => Some(END_OF_GAME_CHARACTER),
+        }
+    }
+}
+
+/// Writes to a dropped connection are discarded, as that's noticed when
+/// reading
+impl FrameSink for TcpStream {
+    fn write_frame(&mut self, frame: &str) -> io::Result<()> {
+        self.write_all(frame.as_bytes()).ok();
+        Ok(())
+    }
+}
+
+impl FrameSink for File {
+    fn write_frame(&mut self, frame: &str) -> io::Result<()> {
+        self.write_all(frame.as_bytes())
+    }
+}
+
+/// A pseudo-terminal. The game plays on its terminal end, in raw mode,
+/// while a program embedding the game drives the controlling end as a
+/// terminal emulator would
+pub struct Pty {
+    pub controller: File,
+    terminal: File,
+}
+
+impl Pty {
+    /// # Errors
+    ///
+    /// Returns an error if no pseudo-terminal is available
+    pub fn open() -> io::Result<Self> {
+        let (mut controller, mut terminal) = (0, 0);
+        let opened = unsafe {
+            libc::openpty(
+                &raw mut controller,
+                &raw mut terminal,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        if opened != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let pty = unsafe {
+            Pty {
+                controller: File::from_raw_fd(controller),
+                terminal: File::from_raw_fd(terminal),
+            }
+        };
+        make_raw(pty.terminal.as_raw_fd());
+        Ok(pty)
+    }
+
+    /// The terminal end, for drawing on
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal can't be shared with the drawing thread
+    pub fn sink(&self) -> io::Result<File> {
+        self.terminal.try_clone()
+    }
+}
+
+impl InputSource for Pty {
+    fn read_key(&mut self, timeout: Option<time::Duration>) -> Option<u8> {
+        read_fd(self.terminal.as_raw_fd(), timeout)
+    }
+}
+
+#[test]
+fn terminal_memory_test() {
+    use crate::board::{self, constants::GAME_OVER};
+    use crate::config::Config;
+    use crate::game::{Best, Game};
+
+    let args = ["--seed=1", "--status=score,moves"].map(String::from);
+    let config = Config::from_args(args.into_iter()).unwrap();
+    let frames = Frames::default();
+    crate::play(&mut Memory::new(b"wasd"), Box::new(frames.clone()), &config).unwrap();
+
+    // The last frame shows the game the keys played, then a farewell
+    let mut game = Game::new(1, &config, Best::default());
+    for key in *b"wasd" {
+        game.act(game.parse(key));
+    }
+    game.over = true;
+    let mut expected = String::new();
+    board::draw_game(&game, &config.layout, &mut expected).unwrap();
+    let frames = frames.take();
+    assert!(frames[frames.len() - 2].ends_with(&expected));
+    assert!(frames[frames.len() - 1].ends_with(GAME_OVER));
+}
+
+#[test]
+fn terminal_pty_test() {
+    use crate::board::constants::GAME_OVER;
+    use crate::config::Config;
+    use std::thread;
+
+    let mut pty = Pty::open().unwrap();
+    let sink = pty.sink().unwrap();
+    let mut controller = pty.controller.try_clone().unwrap();
+    let game = thread::spawn(move || crate::play(&mut pty, Box::new(sink), &Config::default()));
+
+    // Keys typed at the controlling end are read raw, even ^C
+    controller.write_all(b"w\x03").unwrap();
+    let mut screen = String::new();
+    let mut chunk = [0; 4096];
+    let deadline = time::Instant::now() + time::Duration::from_secs(10);
+    while !screen.ends_with(GAME_OVER) {
+        // A game which never ends fails the test rather than hanging it
+        let mut poll = libc::pollfd {
+            fd: controller.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let left = deadline.saturating_duration_since(time::Instant::now());
+        let millis = libc::c_int::try_from(left.as_millis()).unwrap();
+        assert_eq!(
+            unsafe { libc::poll(&raw mut poll, 1, millis) },
+            1,
+            "{screen}"
+        );
+        let read = controller.read(&mut chunk).unwrap();
+        screen.push_str(&String::from_utf8_lossy(&chunk[..read]));
+    }
+    game.join().unwrap().unwrap();
+    assert!(screen.contains("seed "));
+}
@@ -0,0 +1,244 @@
+/*
+  Copyright (c) 2024 Evelyn Lewis
+
+  Permission is hereby granted, free of charge, to any person obtaining a copy
+  of this software and associated documentation files (the "Software"), to deal
+  in the Software without restriction, including without limitation the rights
+  to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+  copies of the Software, and to permit persons to whom the Software is
+  furnished to do so, subject to the following conditions:
+
+  The above copyright notice and this permission notice shall be included in all
+  copies or substantial portions of the Software.
+
+  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+  AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+  OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+  SOFTWARE.
+*/
+
+use std::{
+    array,
+    fmt::{self, Write},
+    io, mem,
+    ops::Deref,
+    sync::{atomic, Arc, Mutex},
+    thread,
+};
+
+use crate::board::{
+    self,
+    constants::{GAME_FAILURE_MESSAGE, LEFT_SPACE, PLAYERS_DRAW, PLAYER_ONE_WINS, PLAYER_TWO_WINS},
+    Action, Direction, Output, Screen,
+};
+use crate::config::Config;
+use crate::game::{Best, Game, Phase, Step};
+use crate::spectate::Publisher;
+use crate::status::Layout;
+use crate::terminal::{InputSource, Tty};
+
+const PLAYERS: usize = 2;
+const ESCAPE: u8 = 0x1B;
+
+/// Keys of both players: `wasd` for player one, and `ijkl` or the arrow
+/// keys for player two. Other keys are shared
+#[derive(Default)]
+struct Keys {
+    /// Bytes of an arrow key's escape sequence read so far
+    escape: usize,
+}
+
+impl Keys {
+    /// The player and action for one byte of input, if it completes a key
+    fn parse(&mut self, input: u8) -> Option<(usize, Action)> {
+        let direction = match (mem::take(&mut self.escape), input) {
+            (_, ESCAPE) => {
+                self.escape = 1;
+                return None;
+            }
+            (1, b'[') => {
+                self.escape = 2;
+                return None;
+            }
+            (2, b'A') | (0, b'i') => Direction::Up,
+            (2, b'B') | (0, b'k') => Direction::Down,
+            (2, b'C') | (0, b'l') => Direction::Right,
+            (2, b'D') | (0, b'j') => Direction::Left,
+            // A lone escape, followed by an ordinary key
+            (1, _) => return self.parse(input),
+            // Any other escape sequence
+            (2, _) => return None,
+            _ => return Some((0, Action::parse(input))),
+        };
+        Some((1, Action::Direction(direction)))
+    }
+}
+
+/// Winner of a finished race, or None for a draw
+///
+/// The first to the target wins. Otherwise, once both players are stuck,
+/// the higher score wins.
+pub(crate) fn winner(games: &[impl Deref<Target = Game>]) -> Option<usize> {
+    if let Some(player) = games.iter().position(|game| game.phase != Phase::Playing) {
+        return Some(player);
+    }
+    let scores: Vec<u32> = games.iter().map(|game| game.board.score()).collect();
+    let best = scores.iter().max()?;
+    match scores.iter().filter(|&score| score == best).count() {
+        1 => scores.iter().position(|score| score == best),
+        _ => None,
+    }
+}
+
+/// Both boards side by side, then the outcome once the race is over
+impl Screen for [Arc<Mutex<Game>>; PLAYERS] {
+    fn draw_frame(&self, layout: &Layout, buffer: &mut String) -> fmt::Result {
+        let games = self.each_ref().map(|game| game.lock().unwrap());
+        let mut frames = [const { String::new() }; PLAYERS];
+        for (frame, game) in frames.iter_mut().zip(&games) {
+            board::draw_game(game, layout, frame)?;
+        }
+        board::compose(&frames, buffer);
+
+        if !games.iter().all(|game| game.over) {
+            return Ok(());
+        }
+        let outcome = match winner(&games) {
+            Some(0) => PLAYER_ONE_WINS,
+            Some(_) => PLAYER_TWO_WINS,
+            None => PLAYERS_DRAW,
+        };
+        write!(buffer, "\r\n{LEFT_SPACE}{outcome}")
+    }
+}
+
+fn is_over(games: &[Arc<Mutex<Game>>]) -> bool {
+    games.iter().all(|game| game.lock().unwrap().over)
+}
+
+/// Race two players side by side, on boards from the same seed
+///
+/// # Errors
+///
+/// Returns an error if the board-drawing thread fails
+///
+/// # Panics
+///
+/// Panics if a game's state is poisoned by another thread
+pub fn run(config: &Config) -> io::Result<()> {
+    let mut seed = config.seed.unwrap_or_else(|| fastrand::u64(..));
+    let seat = |player, seed, best| Game {
+        player: Some(player),
+        ..Game::new(seed, config, best)
+    };
+    let games: [Arc<Mutex<Game>>; PLAYERS] =
+        array::from_fn(|player| Arc::new(Mutex::new(seat(player, seed, Best::default()))));
+
+    let publisher = config
+        .publish
+        .as_deref()
+        .map(Publisher::listen)
+        .transpose()?;
+    let mut tty = Tty::open();
+    let once = tty.is_scripted();
+
+    // Both boards share one drawing thread
+    let done = Arc::new(atomic::AtomicBool::new(false));
+    let draw_join = {
+        let games = games.clone();
+        let layout = config.layout.clone();
+        let done = Arc::clone(&done);
+        thread::spawn(move || {
+            let output = Output {
+                sink: Box::new(io::stdout()),
+                publisher,
+            };
+            board::draw(&games, &layout, output, &done).expect(GAME_FAILURE_MESSAGE);
+        })
+    };
+    draw_join.thread().unpark();
+
+    let mut keys = Keys::default();
+    loop {
+        let Some((player, action)) = tty.read_key(None).and_then(|key| keys.parse(key)) else {
+            continue;
+        };
+        let step = match action {
+            Action::Direction(_) => games[player].lock().unwrap().act(action),
+            Action::NewGame => Step::NewGame,
+            Action::Replay => Step::Replay,
+            Action::Stop if is_over(&games) => Step::Quit,
+            Action::Shutdown => Step::Quit,
+            _ => Step::Unchanged,
+        };
+
+        // Games are locked one at a time, as the drawing thread locks both
+        match step {
+            Step::Unchanged => continue,
+            Step::Changed => {}
+            Step::Over => {
+                let won = {
+                    let mut game = games[player].lock().unwrap();
+                    game.over = true;
+                    game.has_won()
+                };
+                // The first to the target ends the race for both
+                if won {
+                    games[1 - player].lock().unwrap().over = true;
+                }
+                if once && is_over(&games) {
+                    break;
+                }
+            }
+            Step::NewGame | Step::Replay => {
+                if step == Step::NewGame {
+                    seed = fastrand::u64(..);
+                }
+                for (player, game) in games.iter().enumerate() {
+                    let mut game = game.lock().unwrap();
+                    *game = seat(player, seed, game.best);
+                }
+            }
+            Step::Quit => break,
+        }
+        draw_join.thread().unpark();
+    }
+
+    done.store(true, atomic::Ordering::Relaxed);
+    draw_join.thread().unpark();
+    draw_join
+        .join()
+        .map_err(|_| io::Error::other(GAME_FAILURE_MESSAGE))?;
+    Ok(())
+}
+
+#[test]
+fn versus_keys_test() {
+    let mut keys = Keys::default();
+    let up = Action::Direction(Direction::Up);
+    assert!(keys.parse(b'w') == Some((0, up)));
+    assert!(keys.parse(b'i') == Some((1, up)));
+
+    // Arrow keys arrive as escape sequences
+    assert!(keys.parse(ESCAPE).is_none());
+    assert!(keys.parse(b'[').is_none());
+    assert!(keys.parse(b'D') == Some((1, Action::Direction(Direction::Left))));
+    assert!(keys.parse(b'n') == Some((0, Action::NewGame)));
+    assert!(keys.parse(ESCAPE).is_none());
+    assert!(keys.parse(b'w') == Some((0, up)));
+}
+
+#[test]
+fn versus_winner_test() {
+    let config = Config::default();
+    let mut games: [Box<Game>; PLAYERS] =
+        array::from_fn(|_| Box::new(Game::new(1, &config, Best::default())));
+
+    // Equal scores draw, until someone reaches the target
+    assert_eq!(winner(&games), None);
+    games[1].phase = Phase::Won;
+    assert_eq!(winner(&games), Some(1));
+}
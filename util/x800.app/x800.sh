@@ -22,10 +22,10 @@
 # OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 # SOFTWARE.
 
-# x800 gapless gameplay script
+# x800 launcher script
 #
-# This is a convenience script for x800 gameplay, as it provides the ability
-# to play several games in a loop.
+# This is a convenience script for x800 gameplay, as it locates the installed
+# binary. Gapless play is built in: press 'n' for a new game at any time.
 
 set -e
 
@@ -55,15 +55,5 @@ cargo install x800 --git 'https://github.com/evelynlewis/x800.git'
 	exit 1
 fi
 
-# Game loop
-while true; do
-	# Run x800
-	"${BIN_PATH}"
-
-	# Prompt for new game input
-	# Note that the embedded newline and spaces are intentional
-	echo "
-      press enter for a new game
-"
-	prompt
-done
+# Run x800
+exec "${BIN_PATH}" "$@"